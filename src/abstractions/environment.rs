@@ -1,8 +1,9 @@
-use crate::abstractions::physics::{Temperature, Time};
+use crate::abstractions::physics::{Pressure, Temperature, Time};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Environment {
     pub temperature: Temperature,
+    pub pressure: Pressure,
     pub time: Time,
 }
 
@@ -16,6 +17,7 @@ impl Environment {
     pub fn new() -> Self {
         Environment {
             temperature: Temperature::default(),
+            pressure: Pressure::default(),
             time: Time::new(),
         }
     }
@@ -3,7 +3,7 @@ use std::sync::atomic::{AtomicU16, Ordering};
 use super::physics::SpecificHeatCapacity;
 use crate::physics::{PhaseGraph, PhaseGraphBuilder, Solubility, SolubilityBuilder};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct EssenceId {
     id: u16,
 }
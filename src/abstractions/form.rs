@@ -2,7 +2,7 @@ use std::sync::atomic::{AtomicU16, Ordering};
 
 static FORM_COUNTER: AtomicU16 = AtomicU16::new(0);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct FormId {
     id: u16,
 }
@@ -1,48 +1,79 @@
 use std::{
+    fmt::Display,
     iter::Sum,
     ops::{Add, AddAssign, Div, Mul, Sub, SubAssign},
+    str::FromStr,
 };
 
-use super::{Power, TimeSpan};
+use super::{
+    units::{format_trimmed, parse_decimal, split_unit, to_signed, ParseUnitError},
+    Power, TimeSpan,
+};
 
-#[derive(Clone, Copy, PartialEq, PartialOrd)]
+/// Amount of energy. Arithmetic on energy saturates instead of overflowing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Default)]
 pub struct Energy {
-    pub joules: i32,
+    pub joules: i64,
 }
 
 impl Sum for Energy {
     fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
-        Energy {
-            joules: iter.map(|e| e.joules).sum(),
-        }
+        iter.fold(Energy::from(0), |acc, e| acc + e)
     }
 }
 
 impl SubAssign for Energy {
     fn sub_assign(&mut self, rhs: Self) {
-        self.joules -= rhs.joules;
+        *self = *self - rhs;
     }
 }
 
 impl AddAssign for Energy {
     fn add_assign(&mut self, rhs: Self) {
-        self.joules += rhs.joules;
+        *self = *self + rhs;
     }
 }
 
 impl Energy {
-    pub fn from(joules: i32) -> Self {
+    pub fn from(joules: i64) -> Self {
         Energy { joules }
     }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.joules.checked_add(rhs.joules).map(Energy::from)
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.joules.checked_sub(rhs.joules).map(Energy::from)
+    }
+
+    pub fn checked_mul(self, rhs: i64) -> Option<Self> {
+        self.joules.checked_mul(rhs).map(Energy::from)
+    }
+
+    pub fn checked_div(self, rhs: TimeSpan) -> Option<Power> {
+        let ticks = i64::try_from(rhs.ticks).ok()?;
+        self.joules.checked_div(ticks).map(Power::from)
+    }
+
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        Energy::from(self.joules.saturating_add(rhs.joules))
+    }
+
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        Energy::from(self.joules.saturating_sub(rhs.joules))
+    }
+
+    pub fn saturating_mul(self, rhs: i64) -> Self {
+        Energy::from(self.joules.saturating_mul(rhs))
+    }
 }
 
 impl Add for Energy {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Self {
-            joules: self.joules + rhs.joules,
-        }
+        self.saturating_add(rhs)
     }
 }
 
@@ -50,38 +81,58 @@ impl Sub for Energy {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        Self {
-            joules: self.joules - rhs.joules,
-        }
+        self.saturating_sub(rhs)
     }
 }
 
-impl Mul<i32> for Energy {
+impl Mul<i64> for Energy {
     type Output = Energy;
 
-    fn mul(self, rhs: i32) -> Self::Output {
-        Self::Output {
-            joules: self.joules * rhs,
-        }
+    fn mul(self, rhs: i64) -> Self::Output {
+        self.saturating_mul(rhs)
     }
 }
 
-impl Mul<u32> for Energy {
+impl Mul<u64> for Energy {
     type Output = Energy;
 
-    fn mul(self, rhs: u32) -> Self::Output {
-        Self::Output {
-            joules: self.joules * rhs as i32,
-        }
+    fn mul(self, rhs: u64) -> Self::Output {
+        self.saturating_mul(i64::try_from(rhs).unwrap_or(i64::MAX))
     }
 }
 
+// Energy spread over no time at all doesn't amount to any power
 impl Div<TimeSpan> for Energy {
     type Output = Power;
 
     fn div(self, rhs: TimeSpan) -> Self::Output {
-        Self::Output {
-            mwatts: self.joules / rhs.ticks as i32,
+        self.checked_div(rhs).unwrap_or(Power::from(0))
+    }
+}
+
+impl Display for Energy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let joules = self.joules as i128;
+        match joules.abs() {
+            0..=999 => write!(f, "{} J", joules),
+            1_000..=999_999 => write!(f, "{} kJ", format_trimmed(joules, 3)),
+            _ => write!(f, "{} MJ", format_trimmed(joules, 6)),
         }
     }
 }
+
+impl FromStr for Energy {
+    type Err = ParseUnitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (number, unit) = split_unit(s)?;
+        let joules = match unit {
+            "J" => parse_decimal(number, 0)?,
+            "kJ" => parse_decimal(number, 3)?,
+            "MJ" => parse_decimal(number, 6)?,
+            _ => return Err(ParseUnitError::UnknownUnit(String::from(unit))),
+        };
+
+        Ok(Energy::from(to_signed(joules)?))
+    }
+}
@@ -2,26 +2,27 @@ use std::{iter::Sum, ops::Add};
 
 use super::{Energy, Quantity, Temperature};
 
-#[derive(Clone, Copy, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub struct SpecificHeatCapacity {
-    pub joule_mol_per_kelvin: u32,
+    pub joule_mol_per_kelvin: u64,
 }
 
-#[derive(Clone, Copy, PartialEq, PartialOrd)]
+/// Heat capacity of the whole system. Arithmetic on it saturates instead of overflowing.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub struct HeatCapacity {
-    pub joule_per_mkelvin: u32,
+    pub joule_per_mkelvin: u64,
 }
 
-impl From<u32> for HeatCapacity {
-    fn from(value: u32) -> Self {
+impl From<u64> for HeatCapacity {
+    fn from(value: u64) -> Self {
         Self {
             joule_per_mkelvin: value,
         }
     }
 }
 
-impl From<u32> for SpecificHeatCapacity {
-    fn from(value: u32) -> Self {
+impl From<u64> for SpecificHeatCapacity {
+    fn from(value: u64) -> Self {
         Self {
             joule_mol_per_kelvin: value,
         }
@@ -39,12 +40,30 @@ impl Default for SpecificHeatCapacity {
 impl HeatCapacity {
     pub fn from_specific(quantity: Quantity, heat_capacity: SpecificHeatCapacity) -> Self {
         Self {
-            joule_per_mkelvin: heat_capacity.joule_mol_per_kelvin * quantity.mmol,
+            joule_per_mkelvin: heat_capacity
+                .joule_mol_per_kelvin
+                .saturating_mul(quantity.mmol),
         }
     }
 
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.joule_per_mkelvin
+            .checked_add(rhs.joule_per_mkelvin)
+            .map(HeatCapacity::from)
+    }
+
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        HeatCapacity::from(self.joule_per_mkelvin.saturating_add(rhs.joule_per_mkelvin))
+    }
+
+    // Without anything to absorb it, energy can't change the temperature
+    pub fn checked_delta_temp(&self, e: Energy) -> Option<Temperature> {
+        let capacity = i64::try_from(self.joule_per_mkelvin).unwrap_or(i64::MAX);
+        e.joules.checked_div(capacity).map(Temperature::from)
+    }
+
     pub fn get_delta_temp(&self, e: Energy) -> Temperature {
-        Temperature::from(e.joules / (self.joule_per_mkelvin as i32))
+        self.checked_delta_temp(e).unwrap_or(Temperature::from(0))
     }
 }
 
@@ -52,16 +71,12 @@ impl Add for HeatCapacity {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Self {
-            joule_per_mkelvin: self.joule_per_mkelvin + rhs.joule_per_mkelvin,
-        }
+        self.saturating_add(rhs)
     }
 }
 
 impl Sum for HeatCapacity {
     fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
-        HeatCapacity {
-            joule_per_mkelvin: iter.map(|c| c.joule_per_mkelvin).sum(),
-        }
+        iter.fold(HeatCapacity::from(0), |acc, c| acc + c)
     }
 }
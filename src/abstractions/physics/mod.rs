@@ -1,3 +1,6 @@
+mod units;
+pub use units::ParseUnitError;
+
 mod energy;
 pub use energy::Energy;
 
@@ -13,14 +16,20 @@ pub use heat_exchange::get_heat_capacity;
 mod power;
 pub use power::Power;
 
+mod ratio;
+pub use ratio::Ratio;
+
 mod quantity;
 pub use quantity::{PerMol, Quantity, Rate};
 
+mod pressure;
+pub use pressure::Pressure;
+
 mod time;
 pub use time::{Time, TimeSpan};
 
 mod phase_graph;
-pub use phase_graph::{PhaseGraph, PhaseGraphBuilder, PhaseTransition};
+pub use phase_graph::{Hysteresis, PhaseGraph, PhaseGraphBuilder, PhaseTransition, PressureCurve};
 
 mod solubility;
 pub use solubility::{Solubility, SolubilityBuilder};
@@ -1,91 +1,228 @@
-use super::{Energy, Temperature};
+use super::{Energy, Pressure, Ratio, Temperature};
 use crate::FormId;
-use std::{collections::HashMap, ops::Range};
+use std::ops::Range;
 
+// Universal gas constant in mJ/(mol*K)
+const GAS_CONSTANT: i128 = 8314;
+
+/// How the threshold of a transition moves away from its value at standard pressure.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PressureCurve {
+    // Clausius-Clapeyron relation, given the molar enthalpy of the transition
+    ClausiusClapeyron(Energy),
+    // Threshold shifts by given temperature for every kPa above standard pressure
+    Linear(Temperature),
+}
+
+impl PressureCurve {
+    pub fn get_threshold(&self, threshold: Temperature, pressure: Pressure) -> Temperature {
+        let standard = Pressure::standard();
+        match *self {
+            PressureCurve::ClausiusClapeyron(enthalpy) => {
+                if enthalpy.joules == 0 {
+                    return threshold;
+                }
+                let (Some(ln_pressure), Some(ln_standard)) = (
+                    Ratio::from(pressure.pascals).ln(),
+                    Ratio::from(standard.pascals).ln(),
+                ) else {
+                    return Temperature::from(0);
+                };
+
+                // 1 / T' = 1 / T - R * ln(p / p0) / H, so T' = T * H / (H - R * ln(p / p0) * T),
+                // with the logarithm in millionths and temperatures in mK
+                let ln_ratio = ln_pressure as i128 - ln_standard as i128;
+                let enthalpy = enthalpy.joules as i128;
+                let mkelvin = threshold.mkelvin as i128;
+                let denominator = enthalpy.saturating_mul(1_000_000_000_000).saturating_sub(
+                    GAS_CONSTANT
+                        .saturating_mul(ln_ratio)
+                        .saturating_mul(mkelvin),
+                );
+                if denominator == 0 || (denominator > 0) != (enthalpy > 0) {
+                    return Temperature::from(i64::MAX);
+                }
+
+                let mkelvin = mkelvin
+                    .saturating_mul(enthalpy)
+                    .saturating_mul(1_000_000_000_000)
+                    / denominator;
+                Temperature::from(mkelvin.clamp(0, i64::MAX as i128) as i64)
+            }
+            PressureCurve::Linear(per_kpa) => {
+                let delta_pa = pressure.pascals as i128 - standard.pascals as i128;
+                let shift = per_kpa.mkelvin as i128 * delta_pa / 1000;
+                Temperature::from(
+                    (threshold.mkelvin as i128 + shift).clamp(0, i64::MAX as i128) as i64,
+                )
+            }
+        }
+    }
+}
+
+/// Lets the old form persist past the threshold until nucleation is triggered.
+///
+/// Substance can be superheated or supercooled by up to the given margin. It
+/// transitions once the margin is exceeded, when there is a seed of the new
+/// form present, or when the system is agitated. A zero margin means there is
+/// no hysteresis in that direction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hysteresis {
+    pub superheating: Temperature,
+    pub supercooling: Temperature,
+}
+
+/// Transition between two forms of an essence.
+///
+/// Heating past the threshold turns the left form into the right one,
+/// cooling below it turns the right form back into the left one. The
+/// threshold is given at standard pressure, and follows the pressure curve
+/// when there is one.
 #[derive(Clone, Copy)]
 pub struct PhaseTransition {
     pub threshold: Temperature,
     pub joules_per_mol: Energy,
     pub left_form_id: FormId,
     pub right_form_id: FormId,
+    pub pressure_curve: Option<PressureCurve>,
+    pub hysteresis: Option<Hysteresis>,
 }
 
+impl PhaseTransition {
+    pub fn get_threshold(&self, pressure: Pressure) -> Temperature {
+        match self.pressure_curve {
+            Some(curve) => curve.get_threshold(self.threshold, pressure),
+            None => self.threshold,
+        }
+    }
+
+    pub fn get_superheating(&self) -> Temperature {
+        self.hysteresis
+            .map_or(Temperature::from(0), |hysteresis| hysteresis.superheating)
+    }
+
+    pub fn get_supercooling(&self) -> Temperature {
+        self.hysteresis
+            .map_or(Temperature::from(0), |hysteresis| hysteresis.supercooling)
+    }
+}
+
+/// Graph of forms an essence takes at different temperatures.
+///
+/// A form may have several transitions out of it, for example a crystal that
+/// either melts or sublimates. Heating fires the transition with the lowest
+/// threshold first, cooling the one with the highest threshold.
+///
+/// Thresholds are validated and ordered at standard pressure only. Pressure
+/// curves of different transitions can cross, so away from it a form may be
+/// entered at a higher temperature than it is left at. Form transitions
+/// always compare the thresholds at the current pressure.
 pub struct PhaseGraph {
-    transitions_by_threshold: HashMap<Temperature, PhaseTransition>,
+    // Sorted by threshold
+    transitions: Vec<PhaseTransition>,
 }
 
 impl PhaseGraph {
     pub fn get_by_temperature_in_range(&self, range: &Range<Temperature>) -> Vec<&PhaseTransition> {
-        self.transitions_by_threshold
+        self.transitions
             .iter()
-            .filter_map(|(threshold, transition)| {
-                if range.contains(threshold) {
-                    Some(transition)
-                } else {
-                    None
-                }
-            })
+            .filter(|transition| range.contains(&transition.threshold))
             .collect()
     }
 
     pub fn get_by_temperature(&self, temperature: Temperature) -> Option<&PhaseTransition> {
-        self.transitions_by_threshold.get(&temperature)
+        self.transitions
+            .iter()
+            .find(|transition| transition.threshold == temperature)
+    }
+
+    pub fn iter_transitions(&self) -> impl Iterator<Item = &PhaseTransition> {
+        self.transitions.iter()
+    }
+
+    // Transitions out of the form when heated, lowest threshold first
+    pub fn iter_heating_from(&self, form_id: FormId) -> impl Iterator<Item = &PhaseTransition> {
+        self.transitions
+            .iter()
+            .filter(move |transition| transition.left_form_id == form_id)
+    }
+
+    // Transitions out of the form when cooled, highest threshold first
+    pub fn iter_cooling_from(&self, form_id: FormId) -> impl Iterator<Item = &PhaseTransition> {
+        self.transitions
+            .iter()
+            .rev()
+            .filter(move |transition| transition.right_form_id == form_id)
     }
 }
 
 #[derive(Default)]
 pub struct PhaseGraphBuilder {
-    transitions_by_threshold: HashMap<Temperature, PhaseTransition>,
+    transitions: Vec<PhaseTransition>,
 }
 
 impl PhaseGraphBuilder {
+    // A form has to be entered by heating at a lower temperature than it is left
+    // by heating. Otherwise the graph would contradict itself, which also rules
+    // out any cycles.
     pub fn add_transition(&mut self, transition: PhaseTransition) -> &mut Self {
-        let left_transition = self
-            .transitions_by_threshold
-            .iter()
-            .find(|(_, t)| t.right_form_id == transition.left_form_id);
+        if transition.left_form_id == transition.right_form_id {
+            panic!("Transition has to lead into another form")
+        }
 
-        if let Some((_, left_transition)) = left_transition {
-            if left_transition.threshold >= transition.threshold {
-                panic!("There is already a transition leading into this form")
-            }
+        if transition.pressure_curve == Some(PressureCurve::ClausiusClapeyron(Energy::from(0))) {
+            panic!("Clausius-Clapeyron curve needs a non-zero enthalpy")
         }
 
-        let right_transition = self
-            .transitions_by_threshold
-            .iter()
-            .find(|(_, t)| t.left_form_id == transition.right_form_id);
+        for existing in &self.transitions {
+            if existing.left_form_id == transition.left_form_id
+                && existing.right_form_id == transition.right_form_id
+            {
+                panic!("There is already a transition between these forms")
+            }
 
-        if let Some((_, right_transition)) = right_transition {
-            if right_transition.threshold <= transition.threshold {
-                panic!("There is already a transition leading from this form")
+            if existing.right_form_id == transition.left_form_id
+                && existing.threshold >= transition.threshold
+            {
+                panic!(
+                    "There is already a transition leading into this form at a higher temperature"
+                )
+            }
+
+            if existing.left_form_id == transition.right_form_id
+                && existing.threshold <= transition.threshold
+            {
+                panic!(
+                    "There is already a transition leading from this form at a lower temperature"
+                )
             }
-        }
 
-        if !self.transitions_by_threshold.is_empty()
-            && left_transition.is_none()
-            && right_transition.is_none()
-        {
-            panic!("Can't add transitions with gaps!")
+            let is_same_direction = existing.left_form_id == transition.left_form_id
+                || existing.right_form_id == transition.right_form_id;
+            if is_same_direction && existing.threshold == transition.threshold {
+                panic!("There is already a transition of this form at the same temperature")
+            }
         }
 
-        self.transitions_by_threshold
-            .insert(transition.threshold, transition);
+        let index = self
+            .transitions
+            .partition_point(|existing| existing.threshold <= transition.threshold);
+        self.transitions.insert(index, transition);
 
         self
     }
 
     pub fn build(self) -> PhaseGraph {
         PhaseGraph {
-            transitions_by_threshold: self.transitions_by_threshold,
+            transitions: self.transitions,
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::PhaseTransition;
-    use crate::physics::{phase_graph::PhaseGraphBuilder, Energy, Temperature};
+    use super::{PhaseTransition, PressureCurve};
+    use crate::physics::{phase_graph::PhaseGraphBuilder, Energy, Pressure, Temperature};
 
     #[test]
     pub fn can_add_two_transitions() {
@@ -96,16 +233,20 @@ mod test {
             joules_per_mol: Energy::from(5),
             left_form_id: 0.into(),
             right_form_id: 1.into(),
+            pressure_curve: None,
+            hysteresis: None,
         });
         builder.add_transition(PhaseTransition {
             threshold: Temperature::from(100),
             joules_per_mol: Energy::from(10),
             left_form_id: 1.into(),
             right_form_id: 2.into(),
+            pressure_curve: None,
+            hysteresis: None,
         });
 
         let graph = builder.build();
-        assert_eq!(graph.transitions_by_threshold.len(), 2)
+        assert_eq!(graph.transitions.len(), 2)
     }
 
     #[test]
@@ -118,18 +259,21 @@ mod test {
             joules_per_mol: Energy::from(5),
             left_form_id: 0.into(),
             right_form_id: 1.into(),
+            pressure_curve: None,
+            hysteresis: None,
         });
         builder.add_transition(PhaseTransition {
             threshold: Temperature::from(100),
             joules_per_mol: Energy::from(10),
             left_form_id: 1.into(),
             right_form_id: 0.into(),
+            pressure_curve: None,
+            hysteresis: None,
         });
     }
 
     #[test]
-    #[should_panic]
-    pub fn should_panic_when_adding_gaps() {
+    pub fn can_add_transitions_with_gaps() {
         let mut builder = PhaseGraphBuilder::default();
 
         builder.add_transition(PhaseTransition {
@@ -137,12 +281,82 @@ mod test {
             joules_per_mol: Energy::from(5),
             left_form_id: 0.into(),
             right_form_id: 1.into(),
+            pressure_curve: None,
+            hysteresis: None,
         });
         builder.add_transition(PhaseTransition {
             threshold: Temperature::from(100),
             joules_per_mol: Energy::from(10),
             left_form_id: 2.into(),
             right_form_id: 3.into(),
+            pressure_curve: None,
+            hysteresis: None,
+        });
+
+        let graph = builder.build();
+        assert_eq!(graph.transitions.len(), 2)
+    }
+
+    #[test]
+    pub fn can_branch_into_sublimation() {
+        let mut builder = PhaseGraphBuilder::default();
+
+        // Crystal (0) sublimates into gas (2) before it gets a chance to melt into liquid (1)
+        builder.add_transition(PhaseTransition {
+            threshold: Temperature::from(300),
+            joules_per_mol: Energy::from(5),
+            left_form_id: 0.into(),
+            right_form_id: 1.into(),
+            pressure_curve: None,
+            hysteresis: None,
+        });
+        builder.add_transition(PhaseTransition {
+            threshold: Temperature::from(200),
+            joules_per_mol: Energy::from(20),
+            left_form_id: 0.into(),
+            right_form_id: 2.into(),
+            pressure_curve: None,
+            hysteresis: None,
+        });
+        builder.add_transition(PhaseTransition {
+            threshold: Temperature::from(400),
+            joules_per_mol: Energy::from(10),
+            left_form_id: 1.into(),
+            right_form_id: 2.into(),
+            pressure_curve: None,
+            hysteresis: None,
+        });
+
+        let graph = builder.build();
+        let heating = graph.iter_heating_from(0.into()).next().unwrap();
+        assert_eq!(heating.right_form_id, 2.into());
+
+        // Gas condenses into liquid first, since it is the higher threshold
+        let cooling = graph.iter_cooling_from(2.into()).next().unwrap();
+        assert_eq!(cooling.left_form_id, 1.into());
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn should_panic_when_branches_contradict() {
+        let mut builder = PhaseGraphBuilder::default();
+
+        // Liquid (1) cooled below the threshold can't freeze into two different solids at once
+        builder.add_transition(PhaseTransition {
+            threshold: Temperature::from(100),
+            joules_per_mol: Energy::from(5),
+            left_form_id: 0.into(),
+            right_form_id: 1.into(),
+            pressure_curve: None,
+            hysteresis: None,
+        });
+        builder.add_transition(PhaseTransition {
+            threshold: Temperature::from(100),
+            joules_per_mol: Energy::from(5),
+            left_form_id: 2.into(),
+            right_form_id: 1.into(),
+            pressure_curve: None,
+            hysteresis: None,
         });
     }
 
@@ -155,18 +369,24 @@ mod test {
             joules_per_mol: Energy::from(5),
             left_form_id: 0.into(),
             right_form_id: 1.into(),
+            pressure_curve: None,
+            hysteresis: None,
         });
         builder.add_transition(PhaseTransition {
             threshold: Temperature::from(100),
             joules_per_mol: Energy::from(10),
             left_form_id: 1.into(),
             right_form_id: 2.into(),
+            pressure_curve: None,
+            hysteresis: None,
         });
         builder.add_transition(PhaseTransition {
             threshold: Temperature::from(200),
             joules_per_mol: Energy::from(10),
             left_form_id: 2.into(),
             right_form_id: 3.into(),
+            pressure_curve: None,
+            hysteresis: None,
         });
     }
 
@@ -179,6 +399,8 @@ mod test {
             joules_per_mol: Energy::from(5),
             left_form_id: 0.into(),
             right_form_id: 1.into(),
+            pressure_curve: None,
+            hysteresis: None,
         });
 
         let graph = builder.build();
@@ -189,4 +411,58 @@ mod test {
         let transition = graph.get_by_temperature(Temperature::from(10));
         assert!(transition.is_some());
     }
+
+    #[test]
+    pub fn threshold_follows_pressure_curve() {
+        let boiling = PhaseTransition {
+            threshold: Temperature::from(373_000),
+            joules_per_mol: Energy::from(12),
+            left_form_id: 0.into(),
+            right_form_id: 1.into(),
+            pressure_curve: Some(PressureCurve::ClausiusClapeyron(Energy::from(40_650))),
+            hysteresis: None,
+        };
+
+        assert_eq!(
+            boiling.get_threshold(Pressure::standard()),
+            Temperature::from(373_000)
+        );
+
+        // Boils at about 60 °C in a vacuum and at about 120 °C in a sealed vessel
+        let low = boiling.get_threshold(Pressure::from(20_000));
+        assert!((low.to_kelvin() - 332.0).abs() < 1.0);
+        let high = boiling.get_threshold(Pressure::from(200_000));
+        assert!((high.to_kelvin() - 393.0).abs() < 1.0);
+        assert_eq!(
+            boiling.get_threshold(Pressure::from(0)),
+            Temperature::from(0)
+        );
+
+        let flat = PressureCurve::ClausiusClapeyron(Energy::from(0));
+        assert_eq!(
+            flat.get_threshold(Temperature::from(373_000), Pressure::from(20_000)),
+            Temperature::from(373_000)
+        );
+
+        let linear = PressureCurve::Linear(Temperature::from(100));
+        assert_eq!(
+            linear.get_threshold(Temperature::from(373_000), Pressure::from(111_325)),
+            Temperature::from(374_000)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn should_panic_without_enthalpy_for_clausius_clapeyron() {
+        let mut builder = PhaseGraphBuilder::default();
+
+        builder.add_transition(PhaseTransition {
+            threshold: Temperature::from(373_000),
+            joules_per_mol: Energy::from(12),
+            left_form_id: 0.into(),
+            right_form_id: 1.into(),
+            pressure_curve: Some(PressureCurve::ClausiusClapeyron(Energy::from(0))),
+            hysteresis: None,
+        });
+    }
 }
@@ -1,25 +1,74 @@
-use std::ops::{Add, Mul, Neg, Sub};
+use std::{
+    fmt::Display,
+    ops::{Add, Mul, Neg, Sub},
+    str::FromStr,
+};
 
-use super::{energy::Energy, Quantity, TimeSpan};
+use super::{
+    energy::Energy,
+    units::{format_trimmed, parse_decimal, split_unit, to_signed, ParseUnitError},
+    Quantity, TimeSpan,
+};
 
+/// Rate of energy transfer. Arithmetic on power saturates instead of overflowing.
 #[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
 pub struct Power {
-    pub mwatts: i32,
+    pub mwatts: i64,
 }
 
-impl From<i32> for Power {
-    fn from(value: i32) -> Self {
+impl From<i64> for Power {
+    fn from(value: i64) -> Self {
         Power { mwatts: value }
     }
 }
 
+impl Power {
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.mwatts.checked_add(rhs.mwatts).map(Power::from)
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.mwatts.checked_sub(rhs.mwatts).map(Power::from)
+    }
+
+    pub fn checked_mul(self, rhs: Quantity) -> Option<Self> {
+        let mmol = i64::try_from(rhs.mmol).ok()?;
+        self.mwatts.checked_mul(mmol).map(Power::from)
+    }
+
+    pub fn checked_energy(self, rhs: TimeSpan) -> Option<Energy> {
+        let ticks = i64::try_from(rhs.ticks).ok()?;
+        self.mwatts.checked_mul(ticks).map(Energy::from)
+    }
+
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        Power::from(self.mwatts.saturating_add(rhs.mwatts))
+    }
+
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        Power::from(self.mwatts.saturating_sub(rhs.mwatts))
+    }
+
+    pub fn saturating_mul(self, rhs: Quantity) -> Self {
+        Power::from(
+            self.mwatts
+                .saturating_mul(i64::try_from(rhs.mmol).unwrap_or(i64::MAX)),
+        )
+    }
+
+    pub fn saturating_energy(self, rhs: TimeSpan) -> Energy {
+        Energy::from(
+            self.mwatts
+                .saturating_mul(i64::try_from(rhs.ticks).unwrap_or(i64::MAX)),
+        )
+    }
+}
+
 impl Mul<Quantity> for Power {
     type Output = Power;
 
     fn mul(self, rhs: Quantity) -> Self::Output {
-        Self::Output {
-            mwatts: self.mwatts * rhs.mmol as i32,
-        }
+        self.saturating_mul(rhs)
     }
 }
 
@@ -27,9 +76,7 @@ impl Add for Power {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Self {
-            mwatts: self.mwatts + rhs.mwatts,
-        }
+        self.saturating_add(rhs)
     }
 }
 
@@ -37,9 +84,7 @@ impl Sub for Power {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        Self {
-            mwatts: self.mwatts - rhs.mwatts,
-        }
+        self.saturating_sub(rhs)
     }
 }
 
@@ -48,7 +93,7 @@ impl Neg for Power {
 
     fn neg(self) -> Self::Output {
         Power {
-            mwatts: -self.mwatts,
+            mwatts: self.mwatts.saturating_neg(),
         }
     }
 }
@@ -57,8 +102,33 @@ impl Mul<TimeSpan> for Power {
     type Output = Energy;
 
     fn mul(self, rhs: TimeSpan) -> Self::Output {
-        Energy {
-            joules: self.mwatts * rhs.ticks as i32,
+        self.saturating_energy(rhs)
+    }
+}
+
+impl Display for Power {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mwatts = self.mwatts as i128;
+        match mwatts.abs() {
+            0..=999 => write!(f, "{} mW", mwatts),
+            1_000..=999_999 => write!(f, "{} W", format_trimmed(mwatts, 3)),
+            _ => write!(f, "{} kW", format_trimmed(mwatts, 6)),
         }
     }
 }
+
+impl FromStr for Power {
+    type Err = ParseUnitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (number, unit) = split_unit(s)?;
+        let mwatts = match unit {
+            "mW" => parse_decimal(number, 0)?,
+            "W" => parse_decimal(number, 3)?,
+            "kW" => parse_decimal(number, 6)?,
+            _ => return Err(ParseUnitError::UnknownUnit(String::from(unit))),
+        };
+
+        Ok(Power::from(to_signed(mwatts)?))
+    }
+}
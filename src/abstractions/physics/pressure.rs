@@ -0,0 +1,94 @@
+use std::{
+    fmt::Display,
+    ops::{Add, Sub},
+    str::FromStr,
+};
+
+use super::units::{format_trimmed, parse_decimal, split_unit, to_unsigned, ParseUnitError};
+
+// Pressure of the atmosphere at sea level
+const STANDARD_PASCALS: u64 = 101_325;
+
+/// Absolute pressure. Arithmetic on pressure saturates instead of overflowing.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+pub struct Pressure {
+    pub pascals: u64,
+}
+
+impl Default for Pressure {
+    fn default() -> Self {
+        Pressure::standard()
+    }
+}
+
+impl From<u64> for Pressure {
+    fn from(value: u64) -> Self {
+        Pressure { pascals: value }
+    }
+}
+
+impl Pressure {
+    pub fn standard() -> Self {
+        Pressure::from(STANDARD_PASCALS)
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.pascals.checked_add(rhs.pascals).map(Pressure::from)
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.pascals.checked_sub(rhs.pascals).map(Pressure::from)
+    }
+
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        Pressure::from(self.pascals.saturating_add(rhs.pascals))
+    }
+
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        Pressure::from(self.pascals.saturating_sub(rhs.pascals))
+    }
+}
+
+impl Add for Pressure {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.saturating_add(rhs)
+    }
+}
+
+impl Sub for Pressure {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.saturating_sub(rhs)
+    }
+}
+
+impl Display for Pressure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pascals = self.pascals as i128;
+        match pascals {
+            0..=999 => write!(f, "{} Pa", pascals),
+            1_000..=999_999 => write!(f, "{} kPa", format_trimmed(pascals, 3)),
+            _ => write!(f, "{} MPa", format_trimmed(pascals, 6)),
+        }
+    }
+}
+
+impl FromStr for Pressure {
+    type Err = ParseUnitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (number, unit) = split_unit(s)?;
+        let pascals = match unit {
+            "Pa" => parse_decimal(number, 0)?,
+            "kPa" => parse_decimal(number, 3)?,
+            "MPa" => parse_decimal(number, 6)?,
+            "atm" => parse_decimal(number, 3)? * STANDARD_PASCALS as i128 / 1000,
+            _ => return Err(ParseUnitError::UnknownUnit(String::from(unit))),
+        };
+
+        Ok(Pressure::from(to_unsigned(pascals)?))
+    }
+}
@@ -1,35 +1,52 @@
 use std::{
-    fmt::Debug,
+    fmt::{Debug, Display},
     iter::Sum,
     ops::{Add, AddAssign, Div, Mul, Sub, SubAssign},
+    str::FromStr,
 };
 
-use super::TimeSpan;
+use super::{
+    units::{format_trimmed, parse_decimal, split_unit, to_unsigned, ParseUnitError},
+    Ratio, TimeSpan,
+};
 
+/// Amount of substance. Arithmetic on quantities saturates instead of
+/// overflowing or going below zero.
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub struct Quantity {
-    pub mmol: u32,
+    pub mmol: u64,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub struct Rate {
-    pub mmol_per_tick: u32,
+    pub mmol_per_tick: u64,
 }
 
+/// Amount per mol of some other substance, which may be a fraction
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub struct PerMol {
-    pub mmol_per: u32,
+    pub ratio: Ratio,
 }
 
 impl Default for PerMol {
     fn default() -> Self {
-        Self { mmol_per: 1 }
+        Self {
+            ratio: Ratio::one(),
+        }
+    }
+}
+
+impl From<u64> for PerMol {
+    fn from(value: u64) -> Self {
+        Self {
+            ratio: Ratio::from(value),
+        }
     }
 }
 
-impl From<u32> for PerMol {
-    fn from(value: u32) -> Self {
-        Self { mmol_per: value }
+impl From<Ratio> for PerMol {
+    fn from(ratio: Ratio) -> Self {
+        Self { ratio }
     }
 }
 
@@ -37,7 +54,7 @@ impl Mul<Quantity> for PerMol {
     type Output = Quantity;
 
     fn mul(self, rhs: Quantity) -> Self::Output {
-        Self::Output::from(self.mmol_per * rhs.mmol)
+        rhs * self
     }
 }
 
@@ -45,7 +62,7 @@ impl Mul<PerMol> for Quantity {
     type Output = Quantity;
 
     fn mul(self, rhs: PerMol) -> Self::Output {
-        Self::Output::from(self.mmol * rhs.mmol_per)
+        self * rhs.ratio
     }
 }
 
@@ -57,53 +74,60 @@ impl Default for Rate {
     }
 }
 
-impl From<u32> for Rate {
-    fn from(value: u32) -> Self {
+impl From<u64> for Rate {
+    fn from(value: u64) -> Self {
         Rate {
             mmol_per_tick: value,
         }
     }
 }
 
+impl Rate {
+    pub fn checked_mul(self, rhs: TimeSpan) -> Option<Quantity> {
+        self.mmol_per_tick
+            .checked_mul(rhs.ticks)
+            .map(Quantity::from)
+    }
+
+    pub fn saturating_mul(self, rhs: TimeSpan) -> Quantity {
+        Quantity::from(self.mmol_per_tick.saturating_mul(rhs.ticks))
+    }
+}
+
 impl Mul<TimeSpan> for Rate {
     type Output = Quantity;
 
     fn mul(self, rhs: TimeSpan) -> Self::Output {
-        Quantity {
-            mmol: self.mmol_per_tick * rhs.ticks,
-        }
+        self.saturating_mul(rhs)
     }
 }
 
-impl Mul<u32> for Quantity {
+impl Mul<u64> for Quantity {
     type Output = Quantity;
 
-    fn mul(self, rhs: u32) -> Self::Output {
-        Quantity {
-            mmol: self.mmol * rhs,
-        }
+    fn mul(self, rhs: u64) -> Self::Output {
+        self.saturating_mul(rhs)
     }
 }
 
-impl Div<u32> for Quantity {
+// Nothing is left of a quantity split into zero parts
+impl Div<u64> for Quantity {
     type Output = Quantity;
 
-    fn div(self, rhs: u32) -> Self::Output {
-        Quantity {
-            mmol: self.mmol / rhs,
-        }
+    fn div(self, rhs: u64) -> Self::Output {
+        self.checked_div(rhs).unwrap_or(Quantity::none())
     }
 }
 
 impl SubAssign for Quantity {
     fn sub_assign(&mut self, rhs: Self) {
-        self.mmol -= rhs.mmol;
+        *self = *self - rhs;
     }
 }
 
 impl AddAssign for Quantity {
     fn add_assign(&mut self, rhs: Self) {
-        self.mmol += rhs.mmol;
+        *self = *self + rhs;
     }
 }
 
@@ -111,9 +135,7 @@ impl Div<Quantity> for Quantity {
     type Output = PerMol;
 
     fn div(self, rhs: Quantity) -> Self::Output {
-        Self::Output {
-            mmol_per: (self.mmol as f32 / rhs.mmol as f32) as u32,
-        }
+        PerMol::from(Ratio::new(self.mmol, rhs.mmol))
     }
 }
 
@@ -121,6 +143,34 @@ impl Quantity {
     pub fn none() -> Self {
         Quantity { mmol: 0 }
     }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.mmol.checked_add(rhs.mmol).map(Quantity::from)
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.mmol.checked_sub(rhs.mmol).map(Quantity::from)
+    }
+
+    pub fn checked_mul(self, rhs: u64) -> Option<Self> {
+        self.mmol.checked_mul(rhs).map(Quantity::from)
+    }
+
+    pub fn checked_div(self, rhs: u64) -> Option<Self> {
+        self.mmol.checked_div(rhs).map(Quantity::from)
+    }
+
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        Quantity::from(self.mmol.saturating_add(rhs.mmol))
+    }
+
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        Quantity::from(self.mmol.saturating_sub(rhs.mmol))
+    }
+
+    pub fn saturating_mul(self, rhs: u64) -> Self {
+        Quantity::from(self.mmol.saturating_mul(rhs))
+    }
 }
 
 impl Default for Quantity {
@@ -129,8 +179,8 @@ impl Default for Quantity {
     }
 }
 
-impl From<u32> for Quantity {
-    fn from(value: u32) -> Self {
+impl From<u64> for Quantity {
+    fn from(value: u64) -> Self {
         Quantity { mmol: value }
     }
 }
@@ -139,9 +189,7 @@ impl Add for Quantity {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Quantity {
-            mmol: self.mmol + rhs.mmol,
-        }
+        self.saturating_add(rhs)
     }
 }
 
@@ -149,36 +197,91 @@ impl Sub for Quantity {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        Quantity {
-            mmol: self.mmol - rhs.mmol,
-        }
+        self.saturating_sub(rhs)
     }
 }
 
 impl Sum for Quantity {
     fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
-        Quantity {
-            mmol: iter.map(|q| q.mmol).sum(),
+        iter.fold(Quantity::none(), |acc, q| acc + q)
+    }
+}
+
+impl Display for Quantity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.mmol < 1000 {
+            write!(f, "{} mmol", self.mmol)
+        } else {
+            write!(f, "{} mol", format_trimmed(self.mmol as i128, 3))
         }
     }
 }
 
-impl Mul<f32> for Quantity {
-    type Output = Quantity;
+impl FromStr for Quantity {
+    type Err = ParseUnitError;
 
-    fn mul(self, rhs: f32) -> Self::Output {
-        Quantity {
-            mmol: (self.mmol as f32 * rhs) as u32,
-        }
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (number, unit) = split_unit(s)?;
+        let mmol = match unit {
+            "mol" => parse_decimal(number, 3)?,
+            "mmol" => parse_decimal(number, 0)?,
+            _ => return Err(ParseUnitError::UnknownUnit(String::from(unit))),
+        };
+
+        Ok(Quantity::from(to_unsigned(mmol)?))
     }
 }
 
-impl Mul<Quantity> for f32 {
-    type Output = Quantity;
+impl Display for Rate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/tick", Quantity::from(self.mmol_per_tick))
+    }
+}
 
-    fn mul(self, rhs: Quantity) -> Self::Output {
-        Quantity {
-            mmol: (self * rhs.mmol as f32) as u32,
-        }
+#[cfg(test)]
+mod test {
+    use super::{PerMol, Quantity, Rate};
+    use crate::physics::{Energy, Power, Ratio, TimeSpan};
+
+    #[test]
+    fn large_quantities_dont_overflow() {
+        // 100 mol at 1000 mW per mmol over a long tick
+        let power = Power::from(1000) * Quantity::from(100_000);
+        let energy = power * TimeSpan::from(1_000_000);
+
+        assert_eq!(energy, Energy::from(100_000_000_000_000));
+    }
+
+    #[test]
+    fn subtracting_more_than_exists_saturates() {
+        let mut quantity = Quantity::from(10);
+        quantity -= Quantity::from(20);
+
+        assert_eq!(quantity, Quantity::none());
+        assert_eq!(Quantity::from(10).checked_sub(Quantity::from(20)), None);
+    }
+
+    #[test]
+    fn arithmetic_saturates_at_the_limits() {
+        let huge = Quantity::from(u64::MAX);
+
+        assert_eq!(huge + Quantity::from(1), huge);
+        assert_eq!(huge.checked_add(Quantity::from(1)), None);
+        assert_eq!(Rate::from(u64::MAX) * TimeSpan::from(2), huge);
+        assert_eq!(
+            Power::from(i64::MAX) * TimeSpan::from(2),
+            Energy::from(i64::MAX)
+        );
+        assert_eq!(-Power::from(i64::MIN), Power::from(i64::MAX));
+        assert_eq!(Quantity::from(10) / 0, Quantity::none());
+        assert_eq!(Quantity::from(10).checked_div(0), None);
+    }
+
+    #[test]
+    fn per_mol_can_be_a_fraction() {
+        let half = PerMol::from(Ratio::new(1u64, 2u64));
+
+        assert_eq!(Quantity::from(1001) * half, Quantity::from(500));
+        assert_eq!(Quantity::from(500) / Quantity::from(1000), half);
     }
 }
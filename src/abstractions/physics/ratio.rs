@@ -0,0 +1,334 @@
+use std::{
+    fmt::Display,
+    ops::{Add, Div, Mul, Sub},
+    str::FromStr,
+};
+
+use super::{
+    units::{format_trimmed, parse_decimal, split_unit, to_unsigned, ParseUnitError},
+    Power, Quantity,
+};
+
+const MILLIONTHS: u64 = 1_000_000;
+
+// Exponentials and logarithms are computed with a precision of 10^-18
+const SCALE: i128 = 1_000_000_000_000_000_000;
+const LN_2: i128 = 693_147_180_559_945_309;
+const TO_SCALE: i128 = SCALE / MILLIONTHS as i128;
+
+/// Non-negative fixed-point ratio with a precision of a millionth.
+///
+/// Used instead of floating point numbers wherever a quantity is split, so that
+/// the results are the same on every platform. Results of arithmetic are
+/// rounded down and saturate instead of overflowing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Ratio {
+    pub millionths: u64,
+}
+
+impl From<u64> for Ratio {
+    fn from(value: u64) -> Self {
+        Ratio {
+            millionths: value.saturating_mul(MILLIONTHS),
+        }
+    }
+}
+
+fn saturate(value: u128) -> u64 {
+    u64::try_from(value).unwrap_or(u64::MAX)
+}
+
+// e^exponent, with both at SCALE and the result in millionths
+fn exp_scaled(exponent: i128) -> u64 {
+    // Results past these don't fit in a ratio
+    if exponent > 31 * SCALE {
+        return u64::MAX;
+    }
+    if exponent < -15 * SCALE {
+        return 0;
+    }
+
+    // e^x = 2^k * e^r with r in [0, ln 2), so that the series converges fast
+    let k = exponent.div_euclid(LN_2);
+    let r = exponent.rem_euclid(LN_2);
+    let mut sum = SCALE;
+    let mut term = SCALE;
+    let mut n = 1;
+    while term != 0 {
+        term = term * r / SCALE / n;
+        sum += term;
+        n += 1;
+    }
+
+    let shifted = if k >= 0 { sum << k } else { sum >> -k };
+    saturate((shifted / TO_SCALE) as u128)
+}
+
+// Natural logarithm of a non-zero number of millionths, at SCALE
+fn ln_scaled(millionths: u64) -> i128 {
+    // ln x = k * ln 2 + ln v with v in [1, 2)
+    let mut value = millionths as i128 * TO_SCALE;
+    let mut k = 0;
+    while value >= 2 * SCALE {
+        value >>= 1;
+        k += 1;
+    }
+    while value < SCALE {
+        value <<= 1;
+        k -= 1;
+    }
+
+    // ln v = 2 * atanh(z) = 2 * (z + z^3/3 + z^5/5 + ...) with z = (v - 1) / (v + 1)
+    let z = (value - SCALE) * SCALE / (value + SCALE);
+    let z_squared = z * z / SCALE;
+    let mut sum = 0;
+    let mut term = z;
+    let mut n = 1;
+    while term != 0 {
+        sum += term / n;
+        term = term * z_squared / SCALE;
+        n += 2;
+    }
+
+    2 * sum + k * LN_2
+}
+
+impl Ratio {
+    // Ratio of two numbers, zero when the denominator is zero
+    pub fn new(numerator: impl Into<u128>, denominator: impl Into<u128>) -> Self {
+        let denominator = denominator.into();
+        if denominator == 0 {
+            return Ratio::zero();
+        }
+
+        let millionths = numerator.into().saturating_mul(MILLIONTHS as u128) / denominator;
+        Ratio {
+            millionths: saturate(millionths),
+        }
+    }
+
+    pub fn zero() -> Self {
+        Ratio { millionths: 0 }
+    }
+
+    pub fn one() -> Self {
+        Ratio::from(1)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.millionths == 0
+    }
+
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        Ratio {
+            millionths: self.millionths.saturating_add(rhs.millionths),
+        }
+    }
+
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        Ratio {
+            millionths: self.millionths.saturating_sub(rhs.millionths),
+        }
+    }
+
+    pub fn saturating_mul(self, rhs: Self) -> Self {
+        let product = self.millionths as u128 * rhs.millionths as u128;
+        Ratio {
+            millionths: saturate(product / MILLIONTHS as u128),
+        }
+    }
+
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        (rhs.millionths != 0).then(|| Ratio::new(self.millionths, rhs.millionths))
+    }
+
+    // e raised to the exponent given in millionths
+    pub fn exp(exponent: i64) -> Self {
+        Ratio {
+            millionths: exp_scaled(exponent as i128 * TO_SCALE),
+        }
+    }
+
+    // Natural logarithm in millionths, rounded down, none for zero
+    pub fn ln(self) -> Option<i64> {
+        (!self.is_zero()).then(|| ln_scaled(self.millionths).div_euclid(TO_SCALE) as i64)
+    }
+
+    pub fn pow(self, exponent: Self) -> Self {
+        if exponent.millionths.is_multiple_of(MILLIONTHS) {
+            // Whole powers are exact up to rounding of each multiplication
+            let mut result = Ratio::one();
+            let mut base = self;
+            let mut remaining = exponent.millionths / MILLIONTHS;
+            while remaining > 0 {
+                if remaining & 1 == 1 {
+                    result = result.saturating_mul(base);
+                }
+                base = base.saturating_mul(base);
+                remaining >>= 1;
+            }
+            return result;
+        }
+        if self.is_zero() {
+            return Ratio::zero();
+        }
+
+        let exponent = ln_scaled(self.millionths).saturating_mul(exponent.millionths as i128);
+        Ratio {
+            millionths: exp_scaled(exponent / MILLIONTHS as i128),
+        }
+    }
+
+    // Nearest whole number, halves rounded up
+    pub fn round(self) -> u64 {
+        (self.millionths / (MILLIONTHS / 2)).div_ceil(2)
+    }
+
+    // Applies the ratio to the quantity, returning the part lost to rounding
+    // in millionths of a mmol
+    pub fn apply_with_remainder(self, quantity: Quantity) -> (Quantity, u64) {
+        let exact = quantity.mmol as u128 * self.millionths as u128;
+        (
+            Quantity::from(saturate(exact / MILLIONTHS as u128)),
+            (exact % MILLIONTHS as u128) as u64,
+        )
+    }
+}
+
+impl Add for Ratio {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.saturating_add(rhs)
+    }
+}
+
+impl Sub for Ratio {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.saturating_sub(rhs)
+    }
+}
+
+impl Mul for Ratio {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.saturating_mul(rhs)
+    }
+}
+
+impl Div for Ratio {
+    type Output = Self;
+
+    // Dividing by zero saturates, unless there is nothing to divide
+    fn div(self, rhs: Self) -> Self::Output {
+        self.checked_div(rhs).unwrap_or(Ratio {
+            millionths: if self.is_zero() { 0 } else { u64::MAX },
+        })
+    }
+}
+
+impl Mul<Ratio> for Quantity {
+    type Output = Quantity;
+
+    fn mul(self, rhs: Ratio) -> Self::Output {
+        rhs.apply_with_remainder(self).0
+    }
+}
+
+impl Mul<Quantity> for Ratio {
+    type Output = Quantity;
+
+    fn mul(self, rhs: Quantity) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl Mul<Ratio> for Power {
+    type Output = Power;
+
+    fn mul(self, rhs: Ratio) -> Self::Output {
+        // Rounds towards zero, so that heating and cooling are symmetric
+        let product = self.mwatts as i128 * rhs.millionths as i128 / MILLIONTHS as i128;
+        Power::from(product.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+}
+
+impl Display for Ratio {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", format_trimmed(self.millionths as i128, 6))
+    }
+}
+
+impl FromStr for Ratio {
+    type Err = ParseUnitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (number, unit) = split_unit(s)?;
+        let millionths = match unit {
+            "" => parse_decimal(number, 6)?,
+            "%" => parse_decimal(number, 4)?,
+            _ => return Err(ParseUnitError::UnknownUnit(String::from(unit))),
+        };
+
+        Ok(Ratio {
+            millionths: to_unsigned(millionths)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Ratio;
+    use crate::physics::{Power, Quantity};
+
+    #[test]
+    fn ratios_are_exact_and_round_down() {
+        let third = Ratio::new(1u64, 3u64);
+
+        assert_eq!(third.millionths, 333_333);
+        assert_eq!(Quantity::from(1000) * third, Quantity::from(333));
+        assert_eq!(Power::from(-1000) * third, Power::from(-333));
+        assert_eq!(
+            third.apply_with_remainder(Quantity::from(2)),
+            (Quantity::none(), 666_666)
+        );
+        assert_eq!(Ratio::new(5u64, 0u64), Ratio::zero());
+        assert_eq!(
+            Ratio::one() / Ratio::zero(),
+            Ratio {
+                millionths: u64::MAX
+            }
+        );
+        assert_eq!(Ratio::zero() / Ratio::zero(), Ratio::zero());
+        assert_eq!(Ratio::one().checked_div(Ratio::zero()), None);
+        assert_eq!("0.5".parse(), Ok(Ratio::new(1u64, 2u64)));
+        assert_eq!("12.5%".parse(), Ok(Ratio::new(1u64, 8u64)));
+        assert_eq!(Ratio::new(3u64, 2u64).to_string(), "1.5");
+        assert_eq!(Ratio::new(3u64, 2u64).round(), 2);
+        assert_eq!(Ratio::new(7u64, 5u64).round(), 1);
+    }
+
+    #[test]
+    fn exponentials_are_computed_in_fixed_point() {
+        assert_eq!(Ratio::exp(0), Ratio::one());
+        assert_eq!(Ratio::exp(1_000_000).millionths, 2_718_281);
+        assert_eq!(Ratio::exp(-2_500_000).millionths, 82_084);
+        assert_eq!(Ratio::exp(-20_000_000), Ratio::zero());
+        assert_eq!(Ratio::exp(40_000_000).millionths, u64::MAX);
+
+        assert_eq!(Ratio::one().ln(), Some(0));
+        assert_eq!(Ratio::from(2).ln(), Some(693_147));
+        assert_eq!(Ratio::new(1u64, 10u64).ln(), Some(-2_302_586));
+        assert_eq!(Ratio::zero().ln(), None);
+
+        let half = Ratio::new(1u64, 2u64);
+        assert_eq!(Ratio::from(3).pow(Ratio::from(2)), Ratio::from(9));
+        assert_eq!(half.pow(Ratio::from(3)).millionths, 125_000);
+        assert_eq!(Ratio::zero().pow(Ratio::zero()), Ratio::one());
+        assert_eq!(Ratio::from(4).pow(half).millionths, 2_000_000);
+        assert_eq!(Ratio::zero().pow(half), Ratio::zero());
+    }
+}
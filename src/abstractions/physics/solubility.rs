@@ -1,6 +1,6 @@
 use crate::{engine::Essentia, Builder, FormId, Substance};
 
-use super::{quantity::PerMol, Quantity};
+use super::{quantity::PerMol, Quantity, Ratio};
 
 #[derive(Clone, Copy)]
 pub enum Solubility {
@@ -17,11 +17,11 @@ impl Solubility {
         }
     }
 
-    pub fn get_saturation_percent(&self, engine: &Essentia, solvent: &Substance) -> f32 {
+    pub fn get_saturation_percent(&self, engine: &Essentia, solvent: &Substance) -> Ratio {
         match solvent {
-            Substance::Free(_, _) => 0.0,
+            Substance::Free(_, _) => Ratio::zero(),
             Substance::Solution(_, data, solutes) => {
-                solutes
+                let weight = solutes
                     .iter()
                     .map(|(&essence_id, &quantity)| {
                         let solute_essence = engine
@@ -34,9 +34,9 @@ impl Solubility {
                             panic!("Non-solute found in solution!")
                         }
                     })
-                    .sum::<Quantity>()
-                    .mmol as f32
-                    / data.quantity.mmol as f32
+                    .sum::<Quantity>();
+
+                Ratio::new(weight.mmol, data.quantity.mmol)
             }
         }
     }
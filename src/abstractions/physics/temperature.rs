@@ -1,8 +1,18 @@
-use std::ops::{Add, AddAssign, Sub};
+use std::{
+    fmt::Display,
+    ops::{Add, AddAssign, Sub},
+    str::FromStr,
+};
 
+use super::units::{format_fixed, parse_decimal, split_unit, to_signed, ParseUnitError};
+
+// Freezing point of water on the Kelvin scale
+const CELSIUS_ZERO_MKELVIN: i64 = 273_150;
+
+/// Absolute temperature. Arithmetic on temperature saturates instead of overflowing.
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
 pub struct Temperature {
-    pub mkelvin: i32,
+    pub mkelvin: i64,
 }
 
 impl Default for Temperature {
@@ -11,25 +21,62 @@ impl Default for Temperature {
     }
 }
 
-impl From<i32> for Temperature {
-    fn from(value: i32) -> Self {
+impl From<i64> for Temperature {
+    fn from(value: i64) -> Self {
         Temperature { mkelvin: value }
     }
 }
 
+impl Temperature {
+    // Casts from floating point numbers saturate, and so does the shift to Celsius
+    pub fn from_kelvin(kelvin: f64) -> Self {
+        Temperature::from((kelvin * 1000.0).round() as i64)
+    }
+
+    pub fn from_celsius(celsius: f64) -> Self {
+        Temperature::from(((celsius * 1000.0).round() as i64).saturating_add(CELSIUS_ZERO_MKELVIN))
+    }
+
+    pub fn to_kelvin(&self) -> f64 {
+        self.mkelvin as f64 / 1000.0
+    }
+
+    pub fn to_celsius(&self) -> f64 {
+        self.mkelvin.saturating_sub(CELSIUS_ZERO_MKELVIN) as f64 / 1000.0
+    }
+
+    pub fn display_celsius(&self) -> impl Display {
+        CelsiusDisplay(*self)
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.mkelvin.checked_add(rhs.mkelvin).map(Temperature::from)
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.mkelvin.checked_sub(rhs.mkelvin).map(Temperature::from)
+    }
+
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        Temperature::from(self.mkelvin.saturating_add(rhs.mkelvin))
+    }
+
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        Temperature::from(self.mkelvin.saturating_sub(rhs.mkelvin))
+    }
+}
+
 impl Add for Temperature {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Self {
-            mkelvin: self.mkelvin + rhs.mkelvin,
-        }
+        self.saturating_add(rhs)
     }
 }
 
 impl AddAssign for Temperature {
     fn add_assign(&mut self, rhs: Self) {
-        self.mkelvin += rhs.mkelvin;
+        *self = *self + rhs;
     }
 }
 
@@ -37,8 +84,61 @@ impl Sub for Temperature {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        Self {
-            mkelvin: self.mkelvin - rhs.mkelvin,
-        }
+        self.saturating_sub(rhs)
+    }
+}
+
+impl Display for Temperature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} K", format_fixed(self.mkelvin as i128, 3, 2))
+    }
+}
+
+struct CelsiusDisplay(Temperature);
+
+impl Display for CelsiusDisplay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mcelsius = self.0.mkelvin as i128 - CELSIUS_ZERO_MKELVIN as i128;
+        write!(f, "{} °C", format_fixed(mcelsius, 3, 1))
+    }
+}
+
+impl FromStr for Temperature {
+    type Err = ParseUnitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (number, unit) = split_unit(s)?;
+        let mkelvin = match unit {
+            "K" => parse_decimal(number, 3)?,
+            "mK" => parse_decimal(number, 0)?,
+            "°C" | "C" => parse_decimal(number, 3)? + CELSIUS_ZERO_MKELVIN as i128,
+            _ => return Err(ParseUnitError::UnknownUnit(String::from(unit))),
+        };
+
+        Ok(Temperature::from(to_signed(mkelvin)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Temperature;
+
+    #[test]
+    fn celsius_conversions_saturate() {
+        assert_eq!(Temperature::from_celsius(20.0), Temperature::from(293_150));
+        assert_eq!(Temperature::from(293_150).to_celsius(), 20.0);
+
+        assert_eq!(
+            Temperature::from_celsius(f64::MAX),
+            Temperature::from(i64::MAX)
+        );
+        assert_eq!(
+            Temperature::from_celsius(f64::MIN),
+            Temperature::from(i64::MIN + 273_150)
+        );
+        assert_eq!(
+            Temperature::from(i64::MIN).to_celsius(),
+            i64::MIN as f64 / 1000.0
+        );
     }
 }
@@ -1,13 +1,20 @@
-use std::ops::{Add, AddAssign};
+use std::{
+    fmt::Display,
+    ops::{Add, AddAssign},
+    str::FromStr,
+};
 
+use super::units::{parse_decimal, split_unit, to_unsigned, ParseUnitError};
+
+/// Point in simulation time. Arithmetic on time saturates instead of overflowing.
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub struct Time {
-    pub ticks: u32,
+    pub ticks: u64,
 }
 
-#[derive(Clone, Copy, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub struct TimeSpan {
-    pub ticks: u32,
+    pub ticks: u64,
 }
 
 impl Default for TimeSpan {
@@ -16,12 +23,30 @@ impl Default for TimeSpan {
     }
 }
 
-impl From<u32> for TimeSpan {
-    fn from(value: u32) -> Self {
+impl From<u64> for TimeSpan {
+    fn from(value: u64) -> Self {
         TimeSpan { ticks: value }
     }
 }
 
+impl TimeSpan {
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.ticks.checked_add(rhs.ticks).map(TimeSpan::from)
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.ticks.checked_sub(rhs.ticks).map(TimeSpan::from)
+    }
+
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        TimeSpan::from(self.ticks.saturating_add(rhs.ticks))
+    }
+
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        TimeSpan::from(self.ticks.saturating_sub(rhs.ticks))
+    }
+}
+
 impl Default for Time {
     fn default() -> Self {
         Self::new()
@@ -32,20 +57,73 @@ impl Time {
     pub fn new() -> Self {
         Time { ticks: 0 }
     }
+
+    pub fn checked_add(self, rhs: TimeSpan) -> Option<Self> {
+        self.ticks
+            .checked_add(rhs.ticks)
+            .map(|ticks| Time { ticks })
+    }
+
+    pub fn saturating_add(self, rhs: TimeSpan) -> Self {
+        Time {
+            ticks: self.ticks.saturating_add(rhs.ticks),
+        }
+    }
+
+    // Time elapsed since an earlier point, or none if it is in the future
+    pub fn checked_since(self, earlier: Time) -> Option<TimeSpan> {
+        self.ticks.checked_sub(earlier.ticks).map(TimeSpan::from)
+    }
+
+    pub fn saturating_since(self, earlier: Time) -> TimeSpan {
+        TimeSpan::from(self.ticks.saturating_sub(earlier.ticks))
+    }
+}
+
+impl From<u64> for Time {
+    fn from(value: u64) -> Self {
+        Time { ticks: value }
+    }
 }
 
 impl Add<TimeSpan> for Time {
     type Output = Self;
 
     fn add(self, rhs: TimeSpan) -> Self::Output {
-        Time {
-            ticks: self.ticks + rhs.ticks,
-        }
+        self.saturating_add(rhs)
     }
 }
 
 impl AddAssign<TimeSpan> for Time {
     fn add_assign(&mut self, rhs: TimeSpan) {
-        self.ticks += rhs.ticks;
+        *self = *self + rhs;
+    }
+}
+
+impl Display for Time {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "tick {}", self.ticks)
+    }
+}
+
+impl Display for TimeSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.ticks == 1 {
+            write!(f, "1 tick")
+        } else {
+            write!(f, "{} ticks", self.ticks)
+        }
+    }
+}
+
+impl FromStr for TimeSpan {
+    type Err = ParseUnitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (number, unit) = split_unit(s)?;
+        match unit {
+            "tick" | "ticks" | "t" => Ok(TimeSpan::from(to_unsigned(parse_decimal(number, 0)?)?)),
+            _ => Err(ParseUnitError::UnknownUnit(String::from(unit))),
+        }
     }
 }
@@ -0,0 +1,186 @@
+use std::{error::Error, fmt::Display};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseUnitError {
+    Empty,
+    InvalidNumber(String),
+    UnknownUnit(String),
+    OutOfRange,
+}
+
+impl Display for ParseUnitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "no value given"),
+            Self::InvalidNumber(number) => write!(f, "invalid number '{}'", number),
+            Self::UnknownUnit(unit) => write!(f, "unknown unit '{}'", unit),
+            Self::OutOfRange => write!(f, "value is out of range"),
+        }
+    }
+}
+
+impl Error for ParseUnitError {}
+
+// Splits input like "1.5 mol" into its number and unit parts
+pub(super) fn split_unit(input: &str) -> Result<(&str, &str), ParseUnitError> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(ParseUnitError::Empty);
+    }
+
+    let unit_start = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+        .unwrap_or(input.len());
+
+    Ok((input[..unit_start].trim(), input[unit_start..].trim()))
+}
+
+// Parses a decimal number exactly into an integer scaled by 10^decimals.
+// Floats are avoided so that parsing gives the same result everywhere.
+pub(super) fn parse_decimal(number: &str, decimals: u32) -> Result<i128, ParseUnitError> {
+    let invalid = || ParseUnitError::InvalidNumber(String::from(number));
+
+    let (negative, digits) = match number.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, number.strip_prefix('+').unwrap_or(number)),
+    };
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+
+    if integer.is_empty() && fraction.is_empty()
+        || !integer.chars().all(|c| c.is_ascii_digit())
+        || !fraction.chars().all(|c| c.is_ascii_digit())
+        || fraction.len() > decimals as usize
+    {
+        return Err(invalid());
+    }
+
+    let scale = 10i128.pow(decimals);
+    let integer_value = if integer.is_empty() {
+        0
+    } else {
+        integer
+            .parse::<i128>()
+            .map_err(|_| ParseUnitError::OutOfRange)?
+    };
+    let fraction_value = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<i128>().map_err(|_| invalid())?
+            * 10i128.pow(decimals - fraction.len() as u32)
+    };
+
+    let value = integer_value
+        .checked_mul(scale)
+        .and_then(|value| value.checked_add(fraction_value))
+        .ok_or(ParseUnitError::OutOfRange)?;
+
+    Ok(if negative { -value } else { value })
+}
+
+// Formats value scaled by 10^decimals, rounded to the given number of decimal places
+pub(super) fn format_fixed(value: i128, decimals: u32, places: u32) -> String {
+    let divisor = 10i128.pow(decimals - places);
+    let rounded = (value.abs() + divisor / 2) / divisor;
+    let sign = if value < 0 && rounded != 0 { "-" } else { "" };
+
+    if places == 0 {
+        return format!("{}{}", sign, rounded);
+    }
+
+    let scale = 10i128.pow(places);
+    format!(
+        "{}{}.{:0width$}",
+        sign,
+        rounded / scale,
+        rounded % scale,
+        width = places as usize
+    )
+}
+
+// Formats value scaled by 10^decimals without trailing zeroes
+pub(super) fn format_trimmed(value: i128, decimals: u32) -> String {
+    let formatted = format_fixed(value, decimals, decimals);
+    if formatted.contains('.') {
+        String::from(formatted.trim_end_matches('0').trim_end_matches('.'))
+    } else {
+        formatted
+    }
+}
+
+pub(super) fn to_unsigned(value: i128) -> Result<u64, ParseUnitError> {
+    u64::try_from(value).map_err(|_| ParseUnitError::OutOfRange)
+}
+
+pub(super) fn to_signed(value: i128) -> Result<i64, ParseUnitError> {
+    i64::try_from(value).map_err(|_| ParseUnitError::OutOfRange)
+}
+
+#[cfg(test)]
+mod test {
+    use super::ParseUnitError;
+    use crate::physics::{Energy, Power, Pressure, Quantity, Temperature, TimeSpan};
+
+    #[test]
+    fn displays_with_sensible_scaling() {
+        assert_eq!(Temperature::default().to_string(), "293.00 K");
+        assert_eq!(
+            Temperature::from(293_150).display_celsius().to_string(),
+            "20.0 °C"
+        );
+        assert_eq!(Quantity::from(1500).to_string(), "1.5 mol");
+        assert_eq!(Quantity::from(250).to_string(), "250 mmol");
+        assert_eq!(Energy::from(12).to_string(), "12 J");
+        assert_eq!(Energy::from(-2500).to_string(), "-2.5 kJ");
+        assert_eq!(Power::from(500).to_string(), "500 mW");
+        assert_eq!(Power::from(1250).to_string(), "1.25 W");
+        assert_eq!(TimeSpan::from(1).to_string(), "1 tick");
+        assert_eq!(TimeSpan::from(10).to_string(), "10 ticks");
+        assert_eq!(Pressure::standard().to_string(), "101.325 kPa");
+    }
+
+    #[test]
+    fn parses_unit_suffixes() {
+        assert_eq!("350 K".parse(), Ok(Temperature::from(350_000)));
+        assert_eq!("20.5°C".parse(), Ok(Temperature::from(293_650)));
+        assert_eq!("-10 C".parse(), Ok(Temperature::from(263_150)));
+        assert_eq!("1.5 mol".parse(), Ok(Quantity::from(1500)));
+        assert_eq!("20 mmol".parse(), Ok(Quantity::from(20)));
+        assert_eq!("12 J".parse(), Ok(Energy::from(12)));
+        assert_eq!("-1.2 kJ".parse(), Ok(Energy::from(-1200)));
+        assert_eq!("500 mW".parse(), Ok(Power::from(500)));
+        assert_eq!("+0.5 W".parse(), Ok(Power::from(500)));
+        assert_eq!("3 ticks".parse(), Ok(TimeSpan::from(3)));
+        assert_eq!("1 atm".parse(), Ok(Pressure::standard()));
+        assert_eq!("20 kPa".parse(), Ok(Pressure::from(20_000)));
+    }
+
+    #[test]
+    fn display_round_trips_through_parsing() {
+        let quantity = Quantity::from(123_456);
+        assert_eq!(quantity.to_string().parse(), Ok(quantity));
+
+        let power = Power::from(-7_000_001);
+        assert_eq!(power.to_string().parse(), Ok(power));
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        assert_eq!("".parse::<Quantity>(), Err(ParseUnitError::Empty));
+        assert_eq!(
+            "1.5 furlongs".parse::<Quantity>(),
+            Err(ParseUnitError::UnknownUnit(String::from("furlongs")))
+        );
+        assert_eq!(
+            "1.0005 mol".parse::<Quantity>(),
+            Err(ParseUnitError::InvalidNumber(String::from("1.0005")))
+        );
+        assert_eq!(
+            "-1 mol".parse::<Quantity>(),
+            Err(ParseUnitError::OutOfRange)
+        );
+        assert_eq!(
+            "1..5 J".parse::<Energy>(),
+            Err(ParseUnitError::InvalidNumber(String::from("1..5")))
+        );
+    }
+}
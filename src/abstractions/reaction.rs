@@ -2,12 +2,18 @@ use std::ops::Add;
 
 use crate::{
     engine::ReactionContext,
-    physics::{Power, Quantity},
+    physics::{Power, Quantity, Ratio},
     EssenceId, FormId,
 };
 
 use super::substance::SubstanceId;
 
+mod kinetics;
+pub use kinetics::Kinetics;
+
+mod declarative;
+pub use declarative::{DeclarativeReaction, DeclarativeReactionBuilder, Reagent};
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Product {
     Produce(EssenceId, FormId, Quantity),
@@ -21,48 +27,17 @@ impl Product {
     #[cfg(test)]
     fn assert_valid_op(self, rhs: Product) {
         match (self, rhs) {
-            (Product::Produce(eid1, fid1, _), Product::Produce(eid2, fid2, _)) => {
-                if eid1 != eid2 || fid1 != fid2 {
-                    panic!("Invalid addition! Products don't match");
-                }
-            }
-            (Product::Consume(eid1, fid1, _), Product::Consume(eid2, fid2, _)) => {
-                if eid1 != eid2 || fid1 != fid2 {
-                    panic!("Invalid addition! Products don't match");
-                }
-            }
-            (Product::Produce(eid1, fid1, _), Product::Consume(eid2, fid2, _)) => {
-                if eid1 != eid2 || fid1 != fid2 {
-                    panic!("Invalid addition! Products don't match");
-                }
-            }
-            (Product::Consume(eid1, fid1, _), Product::Produce(eid2, fid2, _)) => {
-                if eid1 != eid2 || fid1 != fid2 {
-                    panic!("Invalid addition! Products don't match");
-                }
-            }
-            (Product::Dissolve(eid1, fid1, sid1, _), Product::Dissolve(eid2, fid2, sid2, _)) => {
-                if eid1 != eid2 || fid1 != fid2 || sid1 != sid2 {
-                    panic!("Invalid addition! Products don't match");
-                }
-            }
             (
-                Product::Precipitate(eid1, fid1, sid1, _),
-                Product::Precipitate(eid2, fid2, sid2, _),
-            ) => {
-                if eid1 != eid2 || fid1 != fid2 || sid1 != sid2 {
-                    panic!("Invalid addition! Products don't match");
-                }
-            }
-            (Product::Dissolve(eid1, fid1, sid1, _), Product::Precipitate(eid2, fid2, sid2, _)) => {
-                if eid1 != eid2 || fid1 != fid2 || sid1 != sid2 {
-                    panic!("Invalid addition! Products don't match");
-                }
+                Product::Produce(eid1, fid1, _) | Product::Consume(eid1, fid1, _),
+                Product::Produce(eid2, fid2, _) | Product::Consume(eid2, fid2, _),
+            ) if eid1 != eid2 || fid1 != fid2 => {
+                panic!("Invalid addition! Products don't match");
             }
-            (Product::Precipitate(eid1, fid1, sid1, _), Product::Dissolve(eid2, fid2, sid2, _)) => {
-                if eid1 != eid2 || fid1 != fid2 || sid1 != sid2 {
-                    panic!("Invalid addition! Products don't match");
-                }
+            (
+                Product::Dissolve(eid1, fid1, sid1, _) | Product::Precipitate(eid1, fid1, sid1, _),
+                Product::Dissolve(eid2, fid2, sid2, _) | Product::Precipitate(eid2, fid2, sid2, _),
+            ) if eid1 != eid2 || fid1 != fid2 || sid1 != sid2 => {
+                panic!("Invalid addition! Products don't match");
             }
             _ => {}
         }
@@ -121,7 +96,116 @@ impl Add<Product> for Product {
     }
 }
 
+/// Substance that changes the rate of a reaction without being consumed by it.
+///
+/// Every mol of the catalyst present in the system adds `potency` to the
+/// reaction rate multiplier. When used as an inhibitor the multiplier is
+/// divided by the same amount instead.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Catalyst {
+    pub essence_id: EssenceId,
+    pub form_id: FormId,
+    pub potency: Ratio,
+    pub is_required: bool,
+}
+
+impl Catalyst {
+    pub fn new(essence_id: EssenceId, form_id: FormId, potency: Ratio) -> Self {
+        Catalyst {
+            essence_id,
+            form_id,
+            potency,
+            is_required: false,
+        }
+    }
+
+    // Reaction will not happen at all unless this catalyst is present
+    pub fn required(mut self) -> Self {
+        self.is_required = true;
+        self
+    }
+
+    pub fn get_factor(&self, quantity: Quantity) -> Ratio {
+        Ratio::one() + self.potency * Ratio::new(quantity.mmol, 1000u64)
+    }
+
+    pub fn matches(&self, essence_id: EssenceId, form_id: FormId) -> bool {
+        self.essence_id == essence_id && self.form_id == form_id
+    }
+}
+
+/// Identifies a reaction registered in an engine. Ids are issued in the order
+/// reactions are registered in and are never reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReactionId {
+    id: u64,
+}
+
+impl From<u64> for ReactionId {
+    fn from(value: u64) -> Self {
+        ReactionId { id: value }
+    }
+}
+
+impl From<ReactionId> for u64 {
+    fn from(value: ReactionId) -> Self {
+        value.id
+    }
+}
+
+/// Part of a tick that a reaction runs in. Stages run in the order they are declared in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stage {
+    // Before everything else, sees the environment as it was left by the previous tick
+    Early,
+    // Ordinary reactions between substances
+    #[default]
+    Main,
+    // Dissolution and precipitation
+    Solubility,
+    // Form transitions, which need to know the heat released by all other reactions
+    Transition,
+    // After the form transitions, sees the phases the substances end up in
+    Late,
+}
+
 pub trait Reaction {
-    fn get_priority(&self) -> u8;
     fn react(&self, context: &ReactionContext) -> Vec<Product>;
+
+    fn get_stage(&self) -> Stage {
+        Stage::Main
+    }
+
+    // Order within the stage, reactions with the same priority see the same context
+    fn get_priority(&self) -> u8 {
+        0
+    }
+
+    // Other reactions refer to this one by its name when ordering themselves around it
+    fn get_name(&self) -> Option<&str> {
+        None
+    }
+
+    // Names of the reactions this one has to run after
+    fn get_run_after(&self) -> Vec<&str> {
+        vec![]
+    }
+
+    // Names of the reactions this one has to run before
+    fn get_run_before(&self) -> Vec<&str> {
+        vec![]
+    }
+
+    fn get_catalysts(&self) -> &[Catalyst] {
+        &[]
+    }
+
+    fn get_inhibitors(&self) -> &[Catalyst] {
+        &[]
+    }
+
+    // Lets the network analysis see through reactions defined declaratively
+    fn as_declarative(&self) -> Option<&DeclarativeReaction> {
+        None
+    }
 }
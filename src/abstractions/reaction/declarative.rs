@@ -0,0 +1,643 @@
+use crate::{
+    engine::ReactionContext,
+    physics::{Energy, Quantity, Rate, Ratio},
+    EssenceId, FormId,
+};
+
+use super::{Catalyst, Kinetics, Product, Reaction, Stage};
+
+/// Single participant of a declarative reaction.
+///
+/// `coefficient` is the stoichiometric amount consumed or produced per mol of
+/// reaction progress, while `order` is the exponent applied to the reactant
+/// quantity by the kinetics.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Reagent {
+    pub essence_id: EssenceId,
+    pub form_id: FormId,
+    pub coefficient: u64,
+    pub order: Ratio,
+}
+
+impl Reagent {
+    pub fn new(essence_id: EssenceId, form_id: FormId) -> Self {
+        Reagent {
+            essence_id,
+            form_id,
+            coefficient: 1,
+            order: Ratio::one(),
+        }
+    }
+
+    pub fn with_coefficient(mut self, coefficient: u64) -> Self {
+        self.coefficient = coefficient;
+        self
+    }
+
+    pub fn with_order(mut self, order: Ratio) -> Self {
+        self.order = order;
+        self
+    }
+}
+
+/// Reaction defined by the reagents it turns into products rather than by code.
+///
+/// Reversible reactions also have `reverse_kinetics`, which turn products back
+/// into reactants. Both directions are emitted every tick and netted by the
+/// engine, so the system settles where forward and reverse rates are equal.
+///
+/// `enthalpy` is the energy absorbed per mol of forward progress. Negative
+/// enthalpy makes the reaction exothermic. Heat is always derived from the
+/// actually converted quantity, so it can't disagree with the products.
+pub struct DeclarativeReaction {
+    pub reactants: Vec<Reagent>,
+    pub products: Vec<Reagent>,
+    pub kinetics: Kinetics,
+    pub reverse_kinetics: Option<Kinetics>,
+    pub enthalpy: Energy,
+    pub catalysts: Vec<Catalyst>,
+    pub inhibitors: Vec<Catalyst>,
+    pub stage: Stage,
+    pub priority: u8,
+    pub name: Option<String>,
+    pub run_after: Vec<String>,
+    pub run_before: Vec<String>,
+}
+
+impl DeclarativeReaction {
+    fn get_quantities(reagents: &[Reagent], context: &ReactionContext) -> Vec<(Reagent, Quantity)> {
+        reagents
+            .iter()
+            .map(|reagent| {
+                (
+                    *reagent,
+                    context
+                        .engine
+                        .get_quantity_of(reagent.essence_id, reagent.form_id),
+                )
+            })
+            .collect()
+    }
+
+    // Reaction can't progress further than its limiting reactant allows
+    fn limit_progress(progress: Quantity, reactants: &[(Reagent, Quantity)]) -> Quantity {
+        reactants
+            .iter()
+            .filter(|(reagent, _)| reagent.coefficient > 0)
+            .map(|(reagent, quantity)| *quantity / reagent.coefficient)
+            .fold(
+                progress,
+                |limit, available| {
+                    if available < limit {
+                        available
+                    } else {
+                        limit
+                    }
+                },
+            )
+    }
+
+    fn get_progress_of(
+        kinetics: &Kinetics,
+        reagents: &[Reagent],
+        context: &ReactionContext,
+    ) -> Quantity {
+        let quantities = Self::get_quantities(reagents, context);
+        if quantities
+            .iter()
+            .any(|(_, quantity)| *quantity == Quantity::none())
+        {
+            return Quantity::none();
+        }
+
+        // Catalysts speed up the progress itself, so that the products stay balanced
+        let progress = kinetics.get_progress(
+            &quantities,
+            context.engine.environment.temperature,
+            context.engine.delta_time,
+        ) * context.get_rate_multiplier();
+
+        Self::limit_progress(progress, &quantities)
+    }
+
+    pub fn get_progress(&self, context: &ReactionContext) -> Quantity {
+        Self::get_progress_of(&self.kinetics, &self.reactants, context)
+    }
+
+    pub fn get_reverse_progress(&self, context: &ReactionContext) -> Quantity {
+        match &self.reverse_kinetics {
+            Some(kinetics) => Self::get_progress_of(kinetics, &self.products, context),
+            None => Quantity::none(),
+        }
+    }
+
+    fn get_conversion(from: &[Reagent], to: &[Reagent], progress: Quantity) -> Vec<Product> {
+        let consumed = from.iter().map(|reagent| {
+            Product::Consume(
+                reagent.essence_id,
+                reagent.form_id,
+                progress * reagent.coefficient,
+            )
+        });
+        let produced = to.iter().map(|reagent| {
+            Product::Produce(
+                reagent.essence_id,
+                reagent.form_id,
+                progress * reagent.coefficient,
+            )
+        });
+
+        consumed.chain(produced).collect()
+    }
+
+    pub fn get_products(&self, progress: Quantity) -> Vec<Product> {
+        Self::get_conversion(&self.reactants, &self.products, progress)
+    }
+
+    pub fn get_reverse_products(&self, progress: Quantity) -> Vec<Product> {
+        Self::get_conversion(&self.products, &self.reactants, progress)
+    }
+
+    // Heat released by the reaction is the opposite of the absorbed enthalpy
+    pub fn get_released_heat(&self, progress: Quantity) -> Energy {
+        Energy::from(0) - self.enthalpy * progress.mmol
+    }
+}
+
+impl Reaction for DeclarativeReaction {
+    fn get_stage(&self) -> Stage {
+        self.stage
+    }
+
+    fn get_priority(&self) -> u8 {
+        self.priority
+    }
+
+    fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn get_run_after(&self) -> Vec<&str> {
+        self.run_after.iter().map(String::as_str).collect()
+    }
+
+    fn get_run_before(&self) -> Vec<&str> {
+        self.run_before.iter().map(String::as_str).collect()
+    }
+
+    fn react(&self, context: &ReactionContext) -> Vec<Product> {
+        let mut products = vec![];
+        let mut released = Energy::from(0);
+
+        let progress = self.get_progress(context);
+        if progress > Quantity::none() {
+            products.extend(self.get_products(progress));
+            released += self.get_released_heat(progress);
+        }
+
+        // Reverse direction is netted against the forward one by the engine
+        let reverse_progress = self.get_reverse_progress(context);
+        if reverse_progress > Quantity::none() {
+            products.extend(self.get_reverse_products(reverse_progress));
+            released -= self.get_released_heat(reverse_progress);
+        }
+
+        // Heat is spread over the tick only once, so that the rounding is carried over
+        let power = context.to_power(released);
+        if power.mwatts != 0 {
+            products.push(Product::Thermal(power));
+        }
+
+        products
+    }
+
+    fn get_catalysts(&self) -> &[Catalyst] {
+        &self.catalysts
+    }
+
+    fn get_inhibitors(&self) -> &[Catalyst] {
+        &self.inhibitors
+    }
+
+    fn as_declarative(&self) -> Option<&DeclarativeReaction> {
+        Some(self)
+    }
+}
+
+#[derive(Default)]
+pub struct DeclarativeReactionBuilder {
+    reactants: Vec<Reagent>,
+    products: Vec<Reagent>,
+    kinetics: Kinetics,
+    reverse_kinetics: Option<Kinetics>,
+    equilibrium_constant: Option<Ratio>,
+    reverse_activation_energy: Option<Energy>,
+    enthalpy: Option<Energy>,
+    catalysts: Vec<Catalyst>,
+    inhibitors: Vec<Catalyst>,
+    stage: Stage,
+    priority: u8,
+    name: Option<String>,
+    run_after: Vec<String>,
+    run_before: Vec<String>,
+}
+
+impl DeclarativeReactionBuilder {
+    pub fn build(self) -> DeclarativeReaction {
+        if self.reactants.is_empty() && self.products.is_empty() {
+            panic!("Reaction must have reactants or products!");
+        }
+
+        let mut reverse_kinetics = self.reverse_kinetics;
+        if let Some(constant) = self.equilibrium_constant {
+            if constant.is_zero() {
+                panic!("Equilibrium constant must be positive!");
+            }
+            // K = k_forward / k_reverse at the reference temperature
+            reverse_kinetics = Some(Kinetics {
+                rate_constant: Rate::from(
+                    (Ratio::from(self.kinetics.rate_constant.mmol_per_tick) / constant).round(),
+                ),
+                ..self.kinetics
+            });
+        }
+        if let (Some(kinetics), Some(activation_energy)) =
+            (reverse_kinetics.as_mut(), self.reverse_activation_energy)
+        {
+            kinetics.activation_energy = activation_energy;
+        }
+
+        DeclarativeReaction {
+            reactants: self.reactants,
+            products: self.products,
+            kinetics: self.kinetics,
+            reverse_kinetics,
+            enthalpy: self.enthalpy.unwrap_or(Energy::from(0)),
+            catalysts: self.catalysts,
+            inhibitors: self.inhibitors,
+            stage: self.stage,
+            priority: self.priority,
+            name: self.name,
+            run_after: self.run_after,
+            run_before: self.run_before,
+        }
+    }
+
+    pub fn with_reactant(mut self, reagent: Reagent) -> Self {
+        self.reactants.push(reagent);
+        self
+    }
+
+    pub fn with_product(mut self, reagent: Reagent) -> Self {
+        self.products.push(reagent);
+        self
+    }
+
+    pub fn with_kinetics(mut self, kinetics: Kinetics) -> Self {
+        self.kinetics = kinetics;
+        self
+    }
+
+    pub fn with_rate_constant(mut self, rate: Rate) -> Self {
+        self.kinetics.rate_constant = rate;
+        self
+    }
+
+    pub fn with_activation_energy(mut self, joules_per_mol: Energy) -> Self {
+        self.kinetics.activation_energy = joules_per_mol;
+        self
+    }
+
+    pub fn with_reverse_kinetics(mut self, kinetics: Kinetics) -> Self {
+        self.reverse_kinetics = Some(kinetics);
+        self
+    }
+
+    // Makes the reaction reversible, deriving the reverse rate from the forward one
+    pub fn with_equilibrium_constant(mut self, constant: Ratio) -> Self {
+        self.equilibrium_constant = Some(constant);
+        self
+    }
+
+    // Differing activation energies make the equilibrium shift with temperature
+    pub fn with_reverse_activation_energy(mut self, joules_per_mol: Energy) -> Self {
+        self.reverse_activation_energy = Some(joules_per_mol);
+        self
+    }
+
+    // Energy absorbed per mol of progress, negative for exothermic reactions
+    pub fn with_enthalpy(mut self, joules_per_mol: Energy) -> Self {
+        self.enthalpy = Some(joules_per_mol);
+        self
+    }
+
+    pub fn with_catalyst(mut self, catalyst: Catalyst) -> Self {
+        self.catalysts.push(catalyst);
+        self
+    }
+
+    pub fn with_inhibitor(mut self, inhibitor: Catalyst) -> Self {
+        self.inhibitors.push(inhibitor);
+        self
+    }
+
+    pub fn with_stage(mut self, stage: Stage) -> Self {
+        self.stage = stage;
+        self
+    }
+
+    pub fn with_priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(String::from(name));
+        self
+    }
+
+    pub fn run_after(mut self, name: &str) -> Self {
+        self.run_after.push(String::from(name));
+        self
+    }
+
+    pub fn run_before(mut self, name: &str) -> Self {
+        self.run_before.push(String::from(name));
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DeclarativeReaction, DeclarativeReactionBuilder, Reagent};
+    use crate::{
+        engine::{Essentia, EssentiaBuilder, ReactionContext},
+        physics::{Energy, Power, Quantity, Rate, Ratio, Temperature, TimeSpan},
+        reaction::{Product, Reaction},
+        EssenceBuilder, EssenceId, Form, FormId, SubstanceBuilder,
+    };
+
+    enum Forms {
+        Liquid = 1,
+    }
+
+    impl From<Forms> for FormId {
+        fn from(val: Forms) -> Self {
+            (val as u16).into()
+        }
+    }
+
+    enum Essences {
+        Acid = 1,
+        Base = 2,
+        Brine = 3,
+    }
+
+    impl From<Essences> for EssenceId {
+        fn from(val: Essences) -> Self {
+            (val as u16).into()
+        }
+    }
+
+    fn build_engine() -> Essentia {
+        let mut engine = builder().build();
+        engine.delta_time = TimeSpan::default();
+        engine
+    }
+
+    fn builder() -> EssentiaBuilder {
+        EssentiaBuilder::new()
+            .register_form(Form::new_with_id(Forms::Liquid.into(), "Liquid"))
+            .register_essence(
+                EssenceBuilder::default()
+                    .with_custom_id(Essences::Acid.into())
+                    .build(),
+            )
+            .register_essence(
+                EssenceBuilder::default()
+                    .with_custom_id(Essences::Base.into())
+                    .build(),
+            )
+            .register_essence(
+                EssenceBuilder::default()
+                    .with_custom_id(Essences::Brine.into())
+                    .build(),
+            )
+    }
+
+    fn add(engine: &mut Essentia, essence: Essences, quantity: Quantity) {
+        let substance = SubstanceBuilder::new(engine)
+            .is_normal()
+            .with_essence(essence.into())
+            .with_form(Forms::Liquid.into())
+            .with_quantity(quantity)
+            .build();
+        engine.add_substance(substance);
+    }
+
+    fn acid(coefficient: u64) -> Reagent {
+        Reagent::new(Essences::Acid.into(), Forms::Liquid.into()).with_coefficient(coefficient)
+    }
+
+    fn base() -> Reagent {
+        Reagent::new(Essences::Base.into(), Forms::Liquid.into())
+    }
+
+    fn brine() -> Reagent {
+        Reagent::new(Essences::Brine.into(), Forms::Liquid.into())
+    }
+
+    #[test]
+    fn converts_reactants_into_products() {
+        let mut engine = build_engine();
+        add(&mut engine, Essences::Acid, Quantity::from(2000));
+        add(&mut engine, Essences::Base, Quantity::from(1000));
+
+        let reaction = DeclarativeReactionBuilder::default()
+            .with_reactant(acid(2))
+            .with_reactant(base())
+            .with_product(brine())
+            .with_rate_constant(Rate::from(50))
+            .build();
+
+        let products = reaction.react(&ReactionContext::new(&engine));
+
+        // 50 mmol/tick * 2 mol of acid * 1 mol of base
+        assert_eq!(
+            products,
+            vec![
+                Product::Consume(
+                    Essences::Acid.into(),
+                    Forms::Liquid.into(),
+                    Quantity::from(200)
+                ),
+                Product::Consume(
+                    Essences::Base.into(),
+                    Forms::Liquid.into(),
+                    Quantity::from(100)
+                ),
+                Product::Produce(
+                    Essences::Brine.into(),
+                    Forms::Liquid.into(),
+                    Quantity::from(100)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn does_not_consume_more_than_limiting_reactant() {
+        let mut engine = build_engine();
+        add(&mut engine, Essences::Acid, Quantity::from(300));
+        add(&mut engine, Essences::Base, Quantity::from(10_000));
+
+        let reaction = DeclarativeReactionBuilder::default()
+            .with_reactant(acid(3).with_order(Ratio::zero()))
+            .with_reactant(base().with_order(Ratio::zero()))
+            .with_product(brine())
+            .with_rate_constant(Rate::from(1000))
+            .build();
+
+        let progress = reaction.get_progress(&ReactionContext::new(&engine));
+        assert_eq!(progress, Quantity::from(100));
+    }
+
+    #[test]
+    fn heating_speeds_up_reaction() {
+        let mut engine = build_engine();
+        add(&mut engine, Essences::Acid, Quantity::from(1000));
+
+        let reaction = DeclarativeReactionBuilder::default()
+            .with_reactant(acid(1))
+            .with_product(brine())
+            .with_rate_constant(Rate::from(10))
+            .with_activation_energy(Energy::from(20_000))
+            .build();
+
+        let cold_progress = reaction.get_progress(&ReactionContext::new(&engine));
+        engine.environment.temperature = Temperature::from(350_000);
+        let hot_progress = reaction.get_progress(&ReactionContext::new(&engine));
+
+        assert_eq!(cold_progress, Quantity::from(10));
+        assert!(hot_progress > cold_progress);
+    }
+
+    fn simulate_until_equilibrium(
+        reaction: DeclarativeReaction,
+        temperature: Temperature,
+    ) -> Essentia {
+        let mut engine = builder().register_reaction(Box::new(reaction)).build();
+        engine.environment.temperature = temperature;
+        add(&mut engine, Essences::Acid, Quantity::from(2000));
+
+        engine.simulate(TimeSpan::default());
+        let mut trial = 0;
+        while !engine.is_in_equilibrium {
+            trial += 1;
+            assert!(trial < 1000, "Equilibrium was never reached!");
+            engine.simulate(TimeSpan::default());
+        }
+
+        engine
+    }
+
+    #[test]
+    fn reversible_reaction_settles_at_equilibrium_ratio() {
+        let reaction = DeclarativeReactionBuilder::default()
+            .with_reactant(acid(1))
+            .with_product(brine())
+            .with_rate_constant(Rate::from(300))
+            .with_equilibrium_constant(Ratio::from(3))
+            .build();
+
+        let engine = simulate_until_equilibrium(reaction, Temperature::default());
+
+        // K = 3 means there is three times more product than reactant
+        let acid = engine.get_quantity_of(Essences::Acid.into(), Forms::Liquid.into());
+        let brine = engine.get_quantity_of(Essences::Brine.into(), Forms::Liquid.into());
+        assert_eq!(acid + brine, Quantity::from(2000));
+        assert!(Quantity::from(490) <= acid && acid <= Quantity::from(510));
+    }
+
+    #[test]
+    fn equilibrium_shifts_with_temperature() {
+        let build_reaction = || {
+            DeclarativeReactionBuilder::default()
+                .with_reactant(acid(1))
+                .with_product(brine())
+                .with_rate_constant(Rate::from(100))
+                .with_equilibrium_constant(Ratio::one())
+                .with_reverse_activation_energy(Energy::from(20_000))
+                .build()
+        };
+
+        let reference = simulate_until_equilibrium(build_reaction(), Temperature::default());
+        let heated = simulate_until_equilibrium(build_reaction(), Temperature::from(350_000));
+
+        let reference_acid = reference.get_quantity_of(Essences::Acid.into(), Forms::Liquid.into());
+        let heated_acid = heated.get_quantity_of(Essences::Acid.into(), Forms::Liquid.into());
+        assert!(Quantity::from(980) <= reference_acid && reference_acid <= Quantity::from(1020));
+        // Reverse reaction is more sensitive to heat, so heating favours the reactants
+        assert!(heated_acid > reference_acid);
+    }
+
+    #[test]
+    fn exothermic_reaction_releases_heat_for_converted_quantity() {
+        let mut engine = build_engine();
+        engine.delta_time = TimeSpan::from(2);
+        add(&mut engine, Essences::Acid, Quantity::from(1000));
+
+        let reaction = DeclarativeReactionBuilder::default()
+            .with_reactant(acid(1))
+            .with_product(brine())
+            .with_rate_constant(Rate::from(50))
+            .with_enthalpy(Energy::from(-3))
+            .build();
+
+        let products = reaction.react(&ReactionContext::new(&engine));
+
+        // 100 mmol converted over 2 ticks at 3 joules per mol
+        assert!(products.contains(&Product::Thermal(Power::from(150))));
+    }
+
+    #[test]
+    fn small_heat_is_carried_over() {
+        let mut engine = build_engine();
+        engine.delta_time = TimeSpan::from(3);
+        add(&mut engine, Essences::Acid, Quantity::from(1500));
+
+        let reaction = DeclarativeReactionBuilder::default()
+            .with_reactant(acid(1))
+            .with_product(brine())
+            .with_rate_constant(Rate::from(1))
+            .with_enthalpy(Energy::from(-1))
+            .build();
+
+        // 4 joules for 4 mmol converted over 3 ticks, the last one doesn't make a whole mW
+        let context = ReactionContext::new(&engine);
+        let products = reaction.react(&context);
+        assert!(products.contains(&Product::Thermal(Power::from(1))));
+        assert_eq!(context.remainders.borrow().get_heat(), Energy::from(1));
+    }
+
+    #[test]
+    fn reversible_reaction_heat_cancels_at_equilibrium() {
+        let reaction = DeclarativeReactionBuilder::default()
+            .with_reactant(acid(1))
+            .with_product(brine())
+            .with_rate_constant(Rate::from(100))
+            .with_equilibrium_constant(Ratio::one())
+            .with_enthalpy(Energy::from(5))
+            .build();
+
+        let mut engine = builder().register_reaction(Box::new(reaction)).build();
+        add(&mut engine, Essences::Acid, Quantity::from(1000));
+        add(&mut engine, Essences::Brine, Quantity::from(1000));
+
+        let temperature = engine.environment.temperature;
+        engine.simulate(TimeSpan::default());
+
+        assert!(engine.is_in_equilibrium);
+        assert_eq!(engine.environment.temperature, temperature);
+    }
+}
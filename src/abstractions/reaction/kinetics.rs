@@ -0,0 +1,132 @@
+use crate::physics::{Energy, Quantity, Rate, Ratio, Temperature, TimeSpan};
+
+use super::Reagent;
+
+// Molar gas constant in mJ/(mol*K)
+const GAS_CONSTANT: i128 = 8314;
+
+/// Describes how fast a declarative reaction progresses.
+///
+/// Rate of the reaction follows mass-action law: `rate_constant` is scaled by
+/// quantity (in mol) of each reactant raised to the power of its order.
+/// Temperature dependence is Arrhenius-like: `rate_constant` is the rate at
+/// `reference_temperature` and every reactant is sped up or slowed down
+/// depending on the `activation_energy` (in joules per mol).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Kinetics {
+    pub rate_constant: Rate,
+    pub activation_energy: Energy,
+    pub reference_temperature: Temperature,
+}
+
+impl Default for Kinetics {
+    fn default() -> Self {
+        Self {
+            rate_constant: Rate::default(),
+            activation_energy: Energy::from(0),
+            reference_temperature: Temperature::default(),
+        }
+    }
+}
+
+impl Kinetics {
+    pub fn get_temperature_factor(&self, temperature: Temperature) -> Ratio {
+        if self.activation_energy.joules == 0 {
+            return Ratio::one();
+        }
+        if temperature.mkelvin <= 0 || self.reference_temperature.mkelvin <= 0 {
+            return Ratio::zero();
+        }
+
+        // -Ea / R * (1 / T - 1 / Tref) = Ea * (T - Tref) / (R * T * Tref), in millionths
+        let temperature = temperature.mkelvin as i128;
+        let reference = self.reference_temperature.mkelvin as i128;
+        let exponent = (self.activation_energy.joules as i128)
+            .saturating_mul(temperature - reference)
+            .saturating_mul(1_000_000_000_000)
+            / GAS_CONSTANT
+                .saturating_mul(temperature)
+                .saturating_mul(reference);
+
+        Ratio::exp(exponent.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+
+    pub fn get_concentration_factor(&self, reactants: &[(Reagent, Quantity)]) -> Ratio {
+        reactants
+            .iter()
+            .map(|(reagent, quantity)| Ratio::new(quantity.mmol, 1000u64).pow(reagent.order))
+            .fold(Ratio::one(), Ratio::saturating_mul)
+    }
+
+    // Amount of reaction progress (in mmol) happening over the given time span
+    pub fn get_progress(
+        &self,
+        reactants: &[(Reagent, Quantity)],
+        temperature: Temperature,
+        delta_time: TimeSpan,
+    ) -> Quantity {
+        self.rate_constant.saturating_mul(delta_time)
+            * (self.get_concentration_factor(reactants) * self.get_temperature_factor(temperature))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Kinetics;
+    use crate::{
+        physics::{Energy, Quantity, Rate, Ratio, Temperature, TimeSpan},
+        reaction::Reagent,
+    };
+
+    #[test]
+    fn rate_is_constant_without_activation_energy() {
+        let kinetics = Kinetics::default();
+
+        assert_eq!(
+            kinetics.get_temperature_factor(Temperature::from(10_000)),
+            Ratio::one()
+        );
+        assert_eq!(
+            kinetics.get_temperature_factor(Temperature::from(900_000)),
+            Ratio::one()
+        );
+    }
+
+    #[test]
+    fn rate_increases_with_temperature() {
+        let kinetics = Kinetics {
+            activation_energy: Energy::from(50_000),
+            ..Default::default()
+        };
+
+        let cold = kinetics.get_temperature_factor(Temperature::from(273_000));
+        let reference = kinetics.get_temperature_factor(Temperature::default());
+        let hot = kinetics.get_temperature_factor(Temperature::from(373_000));
+
+        assert!(cold < reference);
+        assert_eq!(reference, Ratio::one());
+        assert!(hot > reference);
+    }
+
+    #[test]
+    fn rate_scales_with_reactant_order() {
+        let kinetics = Kinetics {
+            rate_constant: Rate::from(100),
+            ..Default::default()
+        };
+        let first_order = Reagent::new(0.into(), 0.into());
+        let second_order = Reagent::new(1.into(), 0.into()).with_order(Ratio::from(2));
+
+        let progress = kinetics.get_progress(
+            &[
+                (first_order, Quantity::from(2000)),
+                (second_order, Quantity::from(3000)),
+            ],
+            Temperature::default(),
+            TimeSpan::from(2),
+        );
+
+        // 100 mmol/tick * 2 ticks * 2 mol * (3 mol)^2
+        assert_eq!(progress, Quantity::from(3600));
+    }
+}
@@ -1,24 +1,21 @@
-use std::{
-    collections::HashMap,
-    sync::atomic::{AtomicU16, Ordering},
-};
+use std::collections::BTreeMap;
 
 use crate::{
     abstractions::physics::Quantity, engine::Essentia, physics::Solubility, EssenceId, FormId,
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct SubstanceId {
-    id: u16,
+    id: u64,
 }
 
-impl From<u16> for SubstanceId {
-    fn from(value: u16) -> Self {
+impl From<u64> for SubstanceId {
+    fn from(value: u64) -> Self {
         SubstanceId { id: value }
     }
 }
 
-impl From<SubstanceId> for u16 {
+impl From<SubstanceId> for u64 {
     fn from(value: SubstanceId) -> Self {
         value.id
     }
@@ -31,10 +28,10 @@ pub struct SubstanceData {
     pub quantity: Quantity,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Substance {
     Free(SubstanceId, SubstanceData),
-    Solution(SubstanceId, SubstanceData, HashMap<EssenceId, Quantity>),
+    Solution(SubstanceId, SubstanceData, BTreeMap<EssenceId, Quantity>),
 }
 
 impl Substance {
@@ -95,7 +92,7 @@ pub struct SolutionSubstanceBuilder<'a> {
     form_id: Option<FormId>,
     quantity: Quantity,
 
-    solutes: HashMap<EssenceId, Quantity>,
+    solutes: BTreeMap<EssenceId, Quantity>,
 }
 
 impl Substance {
@@ -118,8 +115,6 @@ impl Substance {
     }
 }
 
-static SUBSTANCE_COUNTER: AtomicU16 = AtomicU16::new(0);
-
 impl<'a> SubstanceBuilder<'a> {
     pub fn new(engine: &'a Essentia) -> Self {
         SubstanceBuilder { engine }
@@ -174,7 +169,7 @@ impl<'a> NormalSubstanceBuilder<'a> {
         }
 
         Substance::Free(
-            SUBSTANCE_COUNTER.fetch_add(1, Ordering::SeqCst).into(),
+            self.engine.next_substance_id(),
             SubstanceData {
                 essence_id: self.essence_id.unwrap(),
                 form_id: self.form_id.unwrap(),
@@ -208,7 +203,7 @@ impl<'a> SolutionSubstanceBuilder<'a> {
 
         Substance::Solution(
             self.substance_id
-                .unwrap_or(SUBSTANCE_COUNTER.fetch_add(1, Ordering::SeqCst).into()),
+                .unwrap_or_else(|| self.engine.next_substance_id()),
             SubstanceData {
                 essence_id: self.essence_id.unwrap(),
                 form_id: self.form_id.unwrap(),
@@ -225,7 +220,7 @@ impl<'a> SolutionSubstanceBuilder<'a> {
             essence_id: None,
             form_id: None,
             quantity: Quantity::default(),
-            solutes: HashMap::new(),
+            solutes: BTreeMap::new(),
         }
     }
 
@@ -236,7 +231,7 @@ impl<'a> SolutionSubstanceBuilder<'a> {
                 self.essence_id = Some(base.essence_id);
                 self.form_id = Some(base.form_id);
                 self.quantity = base.quantity;
-                self.solutes = HashMap::new();
+                self.solutes = BTreeMap::new();
             }
             Substance::Solution(substance_id, base, existing_solutes) => {
                 self.substance_id = Some(substance_id);
@@ -0,0 +1,171 @@
+use crate::physics::{HeatCapacity, Power, Temperature, TimeSpan};
+
+/// Proportional controller holding the system at the target temperature.
+///
+/// Output grows by `gain` for every kelvin the system is off the target and is
+/// capped at `max_power`. It is also never more than needed to reach the target
+/// within a single tick, so that the temperature doesn't overshoot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thermostat {
+    pub target: Temperature,
+    pub gain: Power,
+    pub max_power: Power,
+    pub can_cool: bool,
+}
+
+impl Thermostat {
+    pub fn new(target: Temperature, max_power: Power) -> Self {
+        Thermostat {
+            target,
+            gain: max_power,
+            max_power,
+            can_cool: false,
+        }
+    }
+
+    pub fn with_gain(mut self, gain: Power) -> Self {
+        self.gain = gain;
+        self
+    }
+
+    pub fn with_cooling(mut self) -> Self {
+        self.can_cool = true;
+        self
+    }
+
+    pub fn get_power(
+        &self,
+        temperature: Temperature,
+        heat_capacity: HeatCapacity,
+        delta_time: TimeSpan,
+    ) -> Power {
+        let error = self.target.mkelvin as i128 - temperature.mkelvin as i128;
+        let controlled = (self.gain.mwatts as i128).saturating_mul(error) / 1000;
+
+        let max_power = self.max_power.mwatts.unsigned_abs() as i128;
+        let min_power = if self.can_cool { -max_power } else { 0 };
+        let mut power = controlled.clamp(min_power, max_power);
+
+        if delta_time.ticks > 0 {
+            let to_target = error.saturating_mul(heat_capacity.joule_per_mkelvin as i128)
+                / delta_time.ticks as i128;
+            power = if error >= 0 {
+                power.min(to_target)
+            } else {
+                power.max(to_target)
+            };
+        }
+
+        Power::from(power.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+}
+
+/// Burner or athanor heating the system with a given power.
+///
+/// With a thermostat, the power is controlled to hold the target temperature
+/// instead.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeatSource {
+    pub power: Power,
+    pub thermostat: Option<Thermostat>,
+    pub is_on: bool,
+}
+
+impl HeatSource {
+    // Negative power makes it a chiller
+    pub fn new(power: Power) -> Self {
+        HeatSource {
+            power,
+            thermostat: None,
+            is_on: true,
+        }
+    }
+
+    pub fn with_thermostat(thermostat: Thermostat) -> Self {
+        HeatSource {
+            power: thermostat.max_power,
+            thermostat: Some(thermostat),
+            is_on: true,
+        }
+    }
+
+    pub fn get_power(
+        &self,
+        temperature: Temperature,
+        heat_capacity: HeatCapacity,
+        delta_time: TimeSpan,
+    ) -> Power {
+        match self.thermostat {
+            _ if !self.is_on => Power::from(0),
+            Some(thermostat) => thermostat.get_power(temperature, heat_capacity, delta_time),
+            None => self.power,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{HeatSource, Thermostat};
+    use crate::physics::{HeatCapacity, Power, Temperature, TimeSpan};
+
+    const CAPACITY: HeatCapacity = HeatCapacity {
+        joule_per_mkelvin: 10,
+    };
+
+    #[test]
+    fn thermostat_is_proportional_and_capped() {
+        let thermostat = Thermostat::new(Temperature::from(350_000), Power::from(100_000))
+            .with_gain(Power::from(1_000));
+        let source = HeatSource::with_thermostat(thermostat);
+        let delta_time = TimeSpan::from(1);
+
+        // 10 K off, far from reaching the target in a tick
+        let power = source.get_power(Temperature::from(340_000), CAPACITY, delta_time);
+        assert_eq!(power, Power::from(10_000));
+
+        let power = source.get_power(Temperature::from(200_000), CAPACITY, delta_time);
+        assert_eq!(power, Power::from(100_000));
+
+        // Just enough to reach the target, not to overshoot it
+        let eager = HeatSource::with_thermostat(thermostat.with_gain(Power::from(1_000_000)));
+        let power = eager.get_power(Temperature::from(349_999), CAPACITY, delta_time);
+        assert_eq!(power, Power::from(10));
+    }
+
+    #[test]
+    fn thermostat_cools_only_when_allowed() {
+        let thermostat = Thermostat::new(Temperature::from(300_000), Power::from(100_000));
+        let too_hot = Temperature::from(310_000);
+        let delta_time = TimeSpan::from(1);
+
+        let heater = HeatSource::with_thermostat(thermostat);
+        assert_eq!(
+            heater.get_power(too_hot, CAPACITY, delta_time),
+            Power::from(0)
+        );
+
+        let cooler = HeatSource::with_thermostat(thermostat.with_cooling());
+        assert_eq!(
+            cooler.get_power(too_hot, CAPACITY, delta_time),
+            Power::from(-100_000)
+        );
+    }
+
+    #[test]
+    fn thermostat_survives_extreme_power() {
+        let thermostat = Thermostat::new(Temperature::from(300_000), Power::from(i64::MIN));
+        let source =
+            HeatSource::with_thermostat(thermostat.with_gain(Power::from(i64::MAX)).with_cooling());
+
+        let power = source.get_power(Temperature::from(0), CAPACITY, TimeSpan::from(0));
+        assert_eq!(power, Power::from(i64::MAX));
+
+        let capacity = HeatCapacity {
+            joule_per_mkelvin: u64::MAX,
+        };
+        let power = source.get_power(Temperature::from(i64::MIN), capacity, TimeSpan::from(1));
+        assert_eq!(power, Power::from(i64::MAX));
+        let power = source.get_power(Temperature::from(i64::MAX), capacity, TimeSpan::from(1));
+        assert_eq!(power, Power::from(i64::MIN));
+    }
+}
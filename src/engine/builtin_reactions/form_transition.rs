@@ -1,181 +1,318 @@
-use std::collections::HashMap;
-
-use crate::engine::ReactionContext;
-use crate::physics::{Energy, PhaseTransition, Power, Temperature};
-use crate::reaction::{Product, Reaction};
-use crate::Substance;
+use crate::engine::{ReactionContext, RemainderKey};
+use crate::physics::{Energy, PhaseGraph, PhaseTransition, Power, Quantity, Ratio, Temperature};
+use crate::reaction::{Product, Reaction, Stage};
+use crate::{FormId, Substance};
 
 pub struct FormTransition;
 
 impl FormTransition {
-    fn run_cooling_transition(context: &ReactionContext, power: Power) -> Vec<Product> {
-        let total_energy = power * context.engine.delta_time;
-        let env_temp = context.engine.environment.temperature;
-        let transition_range =
-            env_temp + context.engine.heat_capacity.get_delta_temp(total_energy)..env_temp;
-        let mut transitions_by_thresold =
-            HashMap::<Temperature, Vec<(&PhaseTransition, &Substance)>>::new();
+    pub const NAME: &'static str = "form_transition";
 
-        for (substance, graph) in context.engine.get_with_phase_graphs() {
-            for transition in graph.get_by_temperature_in_range(&transition_range) {
-                if transition.right_form_id == substance.get_form() {
-                    transitions_by_thresold
-                        .entry(transition.threshold)
-                        .and_modify(|entry| (*entry).push((transition, substance)))
-                        .or_insert(vec![(transition, substance)]);
-                }
-            }
-        }
+    // Seed of the new form or agitation lets the substance transition right away
+    fn is_nucleated(context: &ReactionContext, substance: &Substance, form_id: FormId) -> bool {
+        context.engine.is_agitated()
+            || context
+                .engine
+                .get_quantity_of(substance.get_essence(), form_id)
+                > Quantity::none()
+    }
 
-        if transitions_by_thresold.is_empty() {
+    // Superheated or supercooled substances which got nucleated use up the heat
+    // stored past the threshold to transition, which brings the temperature back
+    fn run_nucleation(context: &ReactionContext) -> Vec<Product> {
+        if context.engine.delta_time.ticks == 0 {
             return vec![];
         }
 
-        let mut products = Vec::<Product>::new();
-        let mut remaining_energy = total_energy;
-        for (_, relevant_transitions) in transitions_by_thresold {
-            let total_energy_for_transition = relevant_transitions
-                .iter()
-                .map(|(transition, substance)| {
-                    transition.joules_per_mol * substance.get_quantity().mmol
+        let env_temp = context.engine.environment.temperature;
+        let pressure = context.engine.environment.pressure;
+        let mut products = vec![];
+
+        for (substance, graph) in context.engine.get_with_phase_graphs() {
+            let superheated = graph
+                .iter_heating_from(substance.get_form())
+                .filter(|transition| transition.get_superheating().mkelvin > 0)
+                .map(|transition| (transition.get_threshold(pressure), transition))
+                .filter(|(threshold, transition)| {
+                    *threshold < env_temp
+                        && (env_temp >= *threshold + transition.get_superheating()
+                            || Self::is_nucleated(context, substance, transition.right_form_id))
                 })
-                .sum::<Energy>();
+                .min_by_key(|(threshold, _)| threshold.mkelvin);
 
-            if total_energy_for_transition > remaining_energy {
-                remaining_energy += total_energy_for_transition;
-                products.push(Product::Thermal(
-                    total_energy_for_transition / context.engine.delta_time,
+            if let Some((threshold, transition)) = superheated {
+                let (quantity, energy) =
+                    Self::get_nucleated(context, substance, transition, env_temp - threshold);
+                products.push(Product::Thermal(context.to_power(Energy::from(0) - energy)));
+                products.push(Product::Consume(
+                    substance.get_essence(),
+                    transition.left_form_id,
+                    quantity,
+                ));
+                products.push(Product::Produce(
+                    substance.get_essence(),
+                    transition.right_form_id,
+                    quantity,
+                ));
+                continue;
+            }
+
+            let supercooled = graph
+                .iter_cooling_from(substance.get_form())
+                .filter(|transition| transition.get_supercooling().mkelvin > 0)
+                .map(|transition| (transition.get_threshold(pressure), transition))
+                .filter(|(threshold, transition)| {
+                    *threshold > env_temp
+                        && (env_temp <= *threshold - transition.get_supercooling()
+                            || Self::is_nucleated(context, substance, transition.left_form_id))
+                })
+                .max_by_key(|(threshold, _)| threshold.mkelvin);
+
+            if let Some((threshold, transition)) = supercooled {
+                let (quantity, energy) =
+                    Self::get_nucleated(context, substance, transition, threshold - env_temp);
+                products.push(Product::Thermal(context.to_power(energy)));
+                products.push(Product::Consume(
+                    substance.get_essence(),
+                    transition.right_form_id,
+                    quantity,
+                ));
+                products.push(Product::Produce(
+                    substance.get_essence(),
+                    transition.left_form_id,
+                    quantity,
                 ));
-                for (transition, substance) in relevant_transitions {
-                    products.push(Product::Consume(
-                        substance.get_essence(),
-                        transition.right_form_id,
-                        substance.get_quantity(),
-                    ));
-                    products.push(Product::Produce(
-                        substance.get_essence(),
-                        transition.left_form_id,
-                        substance.get_quantity(),
-                    ));
-                }
-            } else {
-                products.push(Product::Thermal(-power));
-                let transition_percent: f32 =
-                    remaining_energy.joules as f32 / total_energy_for_transition.joules as f32;
-                for (transition, substance) in relevant_transitions {
-                    products.push(Product::Consume(
-                        substance.get_essence(),
-                        transition.right_form_id,
-                        substance.get_quantity() * transition_percent,
-                    ));
-                    products.push(Product::Produce(
-                        substance.get_essence(),
-                        transition.left_form_id,
-                        substance.get_quantity() * transition_percent,
-                    ));
-                }
-                break;
             }
         }
 
         products
     }
 
-    fn run_heating_transition(context: &ReactionContext, power: Power) -> Vec<Product> {
-        let total_energy = power * context.engine.delta_time;
-        let env_temp = context.engine.environment.temperature;
-        let transition_range =
-            env_temp..env_temp + context.engine.heat_capacity.get_delta_temp(total_energy);
-        let mut transitions_by_thresold =
-            HashMap::<Temperature, Vec<(&PhaseTransition, &Substance)>>::new();
+    // Quantity transitioned and latent heat used, limited by the heat stored past the threshold
+    fn get_nucleated(
+        context: &ReactionContext,
+        substance: &Substance,
+        transition: &PhaseTransition,
+        overshoot: Temperature,
+    ) -> (Quantity, Energy) {
+        let stored = Energy::from(overshoot.mkelvin.saturating_mul(
+            i64::try_from(context.engine.heat_capacity.joule_per_mkelvin).unwrap_or(i64::MAX),
+        ));
+        let latent = transition.joules_per_mol * substance.get_quantity().mmol;
 
-        for (substance, graph) in context.engine.get_with_phase_graphs() {
-            for transition in graph.get_by_temperature_in_range(&transition_range) {
-                if transition.left_form_id == substance.get_form() {
-                    transitions_by_thresold
-                        .entry(transition.threshold)
-                        .and_modify(|entry| (*entry).push((transition, substance)))
-                        .or_insert(vec![(transition, substance)]);
-                }
-            }
+        if latent <= stored {
+            (substance.get_quantity(), latent)
+        } else {
+            let transition_ratio = Ratio::new(stored.joules as u64, latent.joules as u64);
+            let key = RemainderKey::Transition(substance.get_essence(), substance.get_form());
+            (
+                context.take_share(key, substance.get_quantity(), transition_ratio),
+                stored,
+            )
         }
+    }
+
+    // First transition out of the form the substance is in, in the direction of the heat flow
+    fn get_next_transition<'a>(
+        context: &ReactionContext,
+        tracked: &Tracked<'a>,
+        temperature: Temperature,
+        is_heating: bool,
+    ) -> Option<(Temperature, &'a PhaseTransition)> {
+        let pressure = context.engine.environment.pressure;
+        let substance = tracked.substance;
+
+        if is_heating {
+            tracked
+                .graph
+                .iter_heating_from(tracked.form_id)
+                .filter(|transition| {
+                    transition.get_superheating().mkelvin == 0
+                        || Self::is_nucleated(context, substance, transition.right_form_id)
+                })
+                .map(|transition| (transition.get_threshold(pressure), transition))
+                .filter(|(threshold, _)| *threshold >= temperature)
+                .min_by_key(|(threshold, _)| threshold.mkelvin)
+        } else {
+            tracked
+                .graph
+                .iter_cooling_from(tracked.form_id)
+                .filter(|transition| {
+                    transition.get_supercooling().mkelvin == 0
+                        || Self::is_nucleated(context, substance, transition.left_form_id)
+                })
+                .map(|transition| (transition.get_threshold(pressure), transition))
+                .filter(|(threshold, _)| *threshold <= temperature)
+                .max_by_key(|(threshold, _)| threshold.mkelvin)
+        }
+    }
 
-        if transitions_by_thresold.is_empty() {
+    /// Moves the temperature along the energy budget of the tick, one crossed threshold at a time.
+    ///
+    /// Reaching a threshold costs the sensible heat in between, after which the
+    /// latent heat of every transition at that threshold is paid. Whatever is
+    /// left carries on towards the next threshold. When the budget runs out
+    /// mid-transition, only a part of the substances transitions and the
+    /// temperature stays at the threshold.
+    ///
+    /// The budget is the energy the engine is about to apply, including the heat
+    /// carried over from earlier ticks.
+    fn run_transitions(context: &ReactionContext, budget: Energy) -> Vec<Product> {
+        let heat_capacity = context.engine.heat_capacity.joule_per_mkelvin as i128;
+        let delta_time = context.engine.delta_time.ticks as i128;
+        let budget = budget.joules as i128;
+        if heat_capacity == 0 || delta_time == 0 || budget == 0 {
             return vec![];
         }
 
+        let is_heating = budget > 0;
+        let direction: i128 = if is_heating { 1 } else { -1 };
+        let initial_temperature = context.engine.environment.temperature;
+
+        let mut tracked = context
+            .engine
+            .get_with_phase_graphs()
+            .map(|(substance, graph)| Tracked {
+                substance,
+                graph,
+                form_id: substance.get_form(),
+            })
+            .collect::<Vec<_>>();
+
         let mut products = Vec::<Product>::new();
-        let mut remaining_energy = total_energy;
-        for (_, relevant_transitions) in transitions_by_thresold {
-            let total_energy_for_transition = relevant_transitions
+        let mut remaining_energy = budget.abs();
+        let mut temperature = initial_temperature;
+
+        loop {
+            let crossing = tracked
                 .iter()
-                .map(|(transition, substance)| {
-                    transition.joules_per_mol * substance.get_quantity().mmol
+                .enumerate()
+                .filter_map(|(index, item)| {
+                    Self::get_next_transition(context, item, temperature, is_heating)
+                        .map(|(threshold, transition)| (index, threshold, transition))
                 })
-                .sum::<Energy>();
+                .collect::<Vec<_>>();
 
-            if total_energy_for_transition < remaining_energy {
-                remaining_energy -= total_energy_for_transition;
-                products.push(Product::Thermal(
-                    -(total_energy_for_transition / context.engine.delta_time),
-                ));
-                for (transition, substance) in relevant_transitions {
-                    products.push(Product::Consume(
-                        substance.get_essence(),
-                        transition.left_form_id,
-                        substance.get_quantity(),
-                    ));
-                    products.push(Product::Produce(
-                        substance.get_essence(),
-                        transition.right_form_id,
-                        substance.get_quantity(),
-                    ));
-                }
+            let next_threshold = if is_heating {
+                crossing
+                    .iter()
+                    .map(|(_, threshold, _)| threshold.mkelvin)
+                    .min()
+            } else {
+                crossing
+                    .iter()
+                    .map(|(_, threshold, _)| threshold.mkelvin)
+                    .max()
+            };
+
+            let Some(next_threshold) = next_threshold else {
+                break;
+            };
+
+            let energy_to_threshold =
+                (next_threshold as i128 - temperature.mkelvin as i128).abs() * heat_capacity;
+            if energy_to_threshold > remaining_energy {
+                break;
+            }
+            remaining_energy -= energy_to_threshold;
+            temperature = Temperature::from(next_threshold);
+
+            let crossing = crossing
+                .into_iter()
+                .filter(|(_, threshold, _)| threshold.mkelvin == next_threshold)
+                .collect::<Vec<_>>();
+
+            let latent_heat = crossing
+                .iter()
+                .map(|(index, _, transition)| {
+                    let quantity = tracked[*index].substance.get_quantity();
+                    (transition.joules_per_mol * quantity.mmol).joules as i128
+                })
+                .sum::<i128>();
+
+            let is_complete = latent_heat <= remaining_energy;
+            let transition_ratio = if is_complete {
+                Ratio::one()
             } else {
-                products.push(Product::Thermal(-power));
-                let transition_percent: f32 =
-                    remaining_energy.joules as f32 / total_energy_for_transition.joules as f32;
-                for (transition, substance) in relevant_transitions {
-                    products.push(Product::Consume(
-                        substance.get_essence(),
-                        transition.left_form_id,
-                        substance.get_quantity() * transition_percent,
-                    ));
-                    products.push(Product::Produce(
-                        substance.get_essence(),
-                        transition.right_form_id,
-                        substance.get_quantity() * transition_percent,
-                    ));
-                }
+                Ratio::new(remaining_energy as u128, latent_heat as u128)
+            };
+
+            for (index, _, transition) in crossing {
+                let item = &mut tracked[index];
+                let (from, to) = if is_heating {
+                    (transition.left_form_id, transition.right_form_id)
+                } else {
+                    (transition.right_form_id, transition.left_form_id)
+                };
+                let essence_id = item.substance.get_essence();
+                let quantity = context.take_share(
+                    RemainderKey::Transition(essence_id, from),
+                    item.substance.get_quantity(),
+                    transition_ratio,
+                );
+
+                products.push(Product::Consume(essence_id, from, quantity));
+                products.push(Product::Produce(essence_id, to, quantity));
+                item.form_id = to;
+            }
+
+            if !is_complete {
+                remaining_energy = 0;
                 break;
             }
+            remaining_energy -= latent_heat;
         }
 
+        if products.is_empty() {
+            return products;
+        }
+
+        // Heat flow is corrected so that the engine applies exactly the sensible heat that is
+        // left. Whatever the power can't express over the tick is carried as heat, so the
+        // temperature doesn't move past a threshold while its transition is incomplete
+        let sensible = (temperature.mkelvin as i128 - initial_temperature.mkelvin as i128)
+            * heat_capacity
+            + direction * remaining_energy;
+        let correction = (sensible - budget).clamp(i64::MIN as i128, i64::MAX as i128);
+        products.push(Product::Thermal(
+            context.to_power(Energy::from(correction as i64)),
+        ));
+
         products
     }
 }
 
+struct Tracked<'a> {
+    substance: &'a Substance,
+    graph: &'a PhaseGraph,
+    // Form the substance is in after the transitions crossed so far
+    form_id: FormId,
+}
+
 impl Reaction for FormTransition {
     fn react(&self, context: &ReactionContext) -> Vec<Product> {
-        let thermal = context
+        let power = context
             .pending_products
             .iter()
-            .find(|p| matches!(p, Product::Thermal(_)));
+            .find_map(|p| match p {
+                Product::Thermal(power) => Some(*power),
+                _ => None,
+            })
+            .unwrap_or(Power::from(0));
+        // Nucleation carries its own rounding, which isn't part of the budget
+        let budget = power * context.engine.delta_time + context.remainders.borrow().get_heat();
 
-        if let Some(&Product::Thermal(power)) = thermal {
-            if power.mwatts > 0 {
-                return FormTransition::run_heating_transition(context, power);
-            } else {
-                return FormTransition::run_cooling_transition(context, power);
-            }
-        }
+        let mut products = FormTransition::run_nucleation(context);
+        products.extend(FormTransition::run_transitions(context, budget));
+
+        products
+    }
 
-        vec![]
+    fn get_stage(&self) -> Stage {
+        Stage::Transition
     }
 
-    // We want the form transitions to occur at the very end of the
-    // simulation pipeline
-    fn get_priority(&self) -> u8 {
-        u8::MAX
+    fn get_name(&self) -> Option<&str> {
+        Some(Self::NAME)
     }
 }
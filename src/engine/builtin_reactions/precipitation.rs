@@ -1,10 +1,10 @@
-use crate::engine::ReactionContext;
-use crate::physics::{Quantity, Solubility};
-use crate::reaction::{Product, Reaction};
+use crate::engine::{ReactionContext, RemainderKey};
+use crate::physics::{Quantity, Ratio, Solubility};
+use crate::reaction::{Product, Reaction, Stage};
 use crate::Substance;
 
 pub struct Precipitation {
-    pub optimal_precipitation_speed_percent: u32,
+    pub optimal_precipitation_speed_percent: u64,
 }
 
 impl Default for Precipitation {
@@ -15,6 +15,10 @@ impl Default for Precipitation {
     }
 }
 
+impl Precipitation {
+    pub const NAME: &'static str = "precipitation";
+}
+
 impl Reaction for Precipitation {
     fn react(&self, context: &ReactionContext) -> Vec<Product> {
         context
@@ -24,46 +28,48 @@ impl Reaction for Precipitation {
                 let precipitation_efficiency = self.get_precipitation_efficiency(
                     solubility.get_saturation_percent(context.engine, solvent),
                 );
-                if precipitation_efficiency > 0.0 {
-                    let absolute_to_precipitate = precipitation_efficiency
-                        * solubility.get_saturation_limit(solvent)
-                        * self.optimal_precipitation_speed_percent
-                        / 100;
-
-                    if absolute_to_precipitate > Quantity::none() {
-                        return self.precipitate(context, solvent, absolute_to_precipitate);
-                    }
-                }
-
-                None
+                let speed = Ratio::new(self.optimal_precipitation_speed_percent, 100u64);
+
+                self.precipitate(
+                    context,
+                    solvent,
+                    solubility.get_saturation_limit(solvent),
+                    precipitation_efficiency * speed,
+                )
             })
             .flatten()
             .collect::<Vec<_>>()
     }
 
-    // Solubility is applied before the form transitions
-    fn get_priority(&self) -> u8 {
-        u8::MAX - 1
+    fn get_stage(&self) -> Stage {
+        Stage::Solubility
+    }
+
+    fn get_name(&self) -> Option<&str> {
+        Some(Self::NAME)
     }
 }
 
 impl Precipitation {
     // This function is balanced to be in equilbrium in dissolution around saturation = 1
-    fn get_precipitation_efficiency(&self, saturation: f32) -> f32 {
-        if saturation < 0.8 {
-            0.1
-        } else if saturation < 1.2 {
-            2.25 * (saturation - 0.8) + 0.1
+    fn get_precipitation_efficiency(&self, saturation: Ratio) -> Ratio {
+        let lower = Ratio::new(8u64, 10u64);
+        if saturation < lower {
+            Ratio::new(1u64, 10u64)
+        } else if saturation < Ratio::new(12u64, 10u64) {
+            (saturation - lower) * Ratio::new(9u64, 4u64) + Ratio::new(1u64, 10u64)
         } else {
-            1.0
+            Ratio::one()
         }
     }
 
+    // Splits the precipitating part of the saturation limit between solutes by their weight
     fn precipitate(
         &self,
         context: &ReactionContext,
         solvent: &Substance,
-        to_precipitate: Quantity,
+        saturation_limit: Quantity,
+        precipitation_ratio: Ratio,
     ) -> Option<Vec<Product>> {
         if let Substance::Solution(_, _, solutes) = solvent {
             let total_weight = solutes
@@ -94,14 +100,22 @@ impl Precipitation {
                 if let Solubility::Solute(precipitate_form, weight) =
                     essence.solubility.expect("Essence is insoluble")
                 {
-                    let precipitation_ratio =
-                        (weight * solute_quantity).mmol as f32 / total_weight.mmol as f32;
-                    products.push(Product::Precipitate(
-                        essence_id,
-                        precipitate_form,
-                        solvent.get_substance(),
-                        to_precipitate * precipitation_ratio,
-                    ))
+                    let weight_ratio =
+                        Ratio::new((weight * solute_quantity).mmol, total_weight.mmol);
+                    let quantity = context.take_share(
+                        RemainderKey::Precipitate(essence_id, solvent.get_substance()),
+                        saturation_limit,
+                        precipitation_ratio * weight_ratio,
+                    );
+
+                    if quantity > Quantity::none() {
+                        products.push(Product::Precipitate(
+                            essence_id,
+                            precipitate_form,
+                            solvent.get_substance(),
+                            quantity,
+                        ))
+                    }
                 }
             }
 
@@ -116,7 +130,7 @@ impl Precipitation {
 mod test {
     use crate::{
         engine::{Essentia, EssentiaBuilder, ReactionContext},
-        physics::{PerMol, Quantity},
+        physics::{PerMol, Quantity, Ratio},
         reaction::{Product, Reaction},
         Builder, EssenceBuilder, EssenceId, Form, FormId, SubstanceBuilder,
     };
@@ -146,11 +160,8 @@ mod test {
         }
     }
 
-    fn build_ctx(engine: &Essentia) -> ReactionContext {
-        ReactionContext {
-            engine: engine,
-            pending_products: vec![],
-        }
+    fn build_ctx(engine: &Essentia) -> ReactionContext<'_> {
+        ReactionContext::new(engine)
     }
 
     fn build_engine() -> Essentia {
@@ -221,7 +232,10 @@ mod test {
             // since solution is less than 80% saturated
             assert_eq!(
                 qty,
-                Quantity::default() * 0.1 * precipitation.optimal_precipitation_speed_percent / 100
+                Quantity::default()
+                    * Ratio::new(1u64, 10u64)
+                    * precipitation.optimal_precipitation_speed_percent
+                    / 100
             );
         } else {
             panic!("Didn't precipitate when expected!");
@@ -323,8 +337,9 @@ mod test {
             .expect("Expected salt to start precipitating!");
 
         assert!(
-            (sugar_precipitation_qty.mmol as f32 / salt_precipitation_qty.mmol as f32) - 2.0
-                < f32::EPSILON
+            ((sugar_precipitation_qty.mmol as f32 / salt_precipitation_qty.mmol as f32) - 2.0)
+                .abs()
+                < 0.1
         )
     }
 }
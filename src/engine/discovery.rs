@@ -0,0 +1,157 @@
+use crate::{
+    engine::{Condition, Essentia, Recipe, RecipeBuilder, ScheduledAction},
+    physics::{Power, Quantity, TimeSpan},
+    EssenceId, FormId,
+};
+
+/// Operation the search is allowed to perform on the system.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operation {
+    Add(EssenceId, FormId, Quantity),
+    // Cools the system down with negative power
+    Heat(Power, TimeSpan),
+    Wait(TimeSpan),
+}
+
+impl Operation {
+    fn append_to(self, builder: RecipeBuilder) -> RecipeBuilder {
+        match self {
+            Operation::Add(essence_id, form_id, quantity) => {
+                builder.with_action(ScheduledAction::AddSubstance(essence_id, form_id, quantity))
+            }
+            Operation::Heat(power, duration) => builder
+                .with_action(ScheduledAction::InjectThermal(power, duration))
+                .with_wait(duration),
+            Operation::Wait(duration) => builder.with_wait(duration),
+        }
+    }
+}
+
+/// Bounded breadth-first search for a sequence of operations reaching the target.
+///
+/// Every level extends each candidate by every allowed operation. Only the
+/// candidates meeting most of the target conditions are carried over to
+/// the next level.
+pub struct Discovery {
+    pub operations: Vec<Operation>,
+    pub target: Vec<Condition>,
+    pub max_depth: usize,
+    pub max_breadth: usize,
+}
+
+impl Discovery {
+    // Each candidate is evaluated on a fork of the system its prefix left behind,
+    // so only the operation it adds has to be simulated
+    pub fn search(&self, engine: &Essentia) -> Option<Recipe> {
+        let mut frontier: Vec<(Vec<Operation>, Essentia)> = vec![(vec![], engine.fork())];
+
+        for _ in 0..self.max_depth {
+            let mut candidates = vec![];
+            for (prefix, simulated) in &frontier {
+                for &operation in &self.operations {
+                    let mut candidate = prefix.clone();
+                    candidate.push(operation);
+
+                    let mut fork = simulated.fork();
+                    operation
+                        .append_to(RecipeBuilder::default())
+                        .build()
+                        .run(&mut fork);
+
+                    let score = self
+                        .target
+                        .iter()
+                        .filter(|condition| condition.is_met(&fork))
+                        .count();
+
+                    if score == self.target.len() {
+                        return Some(self.to_recipe(&candidate));
+                    }
+
+                    candidates.push((score, candidate, fork));
+                }
+            }
+
+            // Stable, so earlier (simpler) candidates win ties
+            candidates.sort_by_key(|(score, _, _)| std::cmp::Reverse(*score));
+            frontier = candidates
+                .into_iter()
+                .take(self.max_breadth)
+                .map(|(_, candidate, fork)| (candidate, fork))
+                .collect();
+        }
+
+        None
+    }
+
+    pub fn to_recipe(&self, operations: &[Operation]) -> Recipe {
+        let builder = operations
+            .iter()
+            .fold(RecipeBuilder::default(), |builder, operation| {
+                operation.append_to(builder)
+            });
+
+        self.target
+            .iter()
+            .fold(builder, |builder, &condition| {
+                builder.with_expectation(condition)
+            })
+            .build()
+    }
+}
+
+pub struct DiscoveryBuilder {
+    operations: Vec<Operation>,
+    target: Vec<Condition>,
+    max_depth: usize,
+    max_breadth: usize,
+}
+
+impl Default for DiscoveryBuilder {
+    fn default() -> Self {
+        Self {
+            operations: vec![],
+            target: vec![],
+            max_depth: 4,
+            max_breadth: 8,
+        }
+    }
+}
+
+impl DiscoveryBuilder {
+    pub fn with_operation(mut self, operation: Operation) -> Self {
+        self.operations.push(operation);
+        self
+    }
+
+    pub fn with_target(mut self, condition: Condition) -> Self {
+        self.target.push(condition);
+        self
+    }
+
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn with_max_breadth(mut self, max_breadth: usize) -> Self {
+        self.max_breadth = max_breadth;
+        self
+    }
+
+    pub fn build(self) -> Discovery {
+        if self.operations.is_empty() {
+            panic!("Discovery needs at least one allowed operation!");
+        }
+        if self.target.is_empty() {
+            panic!("Discovery needs a target to search for!");
+        }
+
+        Discovery {
+            operations: self.operations,
+            target: self.target,
+            max_depth: self.max_depth,
+            max_breadth: self.max_breadth,
+        }
+    }
+}
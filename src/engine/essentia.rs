@@ -1,8 +1,9 @@
-use std::collections::HashMap;
+use std::{cell::Cell, collections::BTreeMap, rc::Rc};
 
 use crate::{
     abstractions::{Environment, Essence, Form, Substance, SubstanceId},
-    physics::{HeatCapacity, TimeSpan},
+    engine::{HeatSource, Remainders, Schedule, ScheduledAction},
+    physics::{HeatCapacity, Time, TimeSpan},
     EssenceId, FormId,
 };
 
@@ -13,20 +14,83 @@ pub struct Essentia {
     pub heat_capacity: HeatCapacity,
     pub delta_time: TimeSpan,
     pub is_in_equilibrium: bool,
+    pub schedule: Schedule,
+    pub heat_sources: Vec<HeatSource>,
+    // Randomness available to reactions is derived from it
+    pub seed: u64,
 
-    substances: HashMap<SubstanceId, Substance>,
-    essence_lookup: HashMap<EssenceId, Essence>,
-    form_lookup: HashMap<FormId, Form>,
-    reactions: ReactionLookup,
+    substances: BTreeMap<SubstanceId, Substance>,
+    registry: Rc<Registry>,
+    // Shared with forks and snapshots until it is changed
+    reactions: Rc<ReactionLookup>,
+    remainders: Remainders,
+    history: Option<History>,
+
+    // Substance ids are issued per engine, so they don't depend on other engines in the process
+    substance_counter: Cell<u64>,
+    is_agitated: bool,
+    check_invariants: bool,
+    invariant_violations: Vec<InvariantViolation>,
+    keep_reports: bool,
+    reports: Vec<TickReport>,
 }
 
 impl Essentia {
     pub fn get_essence(&self, id: EssenceId) -> Option<&Essence> {
-        self.essence_lookup.get(&id)
+        self.registry.get_essence(id)
     }
 
     pub fn get_form(&self, id: FormId) -> Option<&Form> {
-        self.form_lookup.get(&id)
+        self.registry.get_form(id)
+    }
+
+    pub fn get_registry(&self) -> &Rc<Registry> {
+        &self.registry
+    }
+
+    /// Copies the state of the engine into a new one, sharing the registry.
+    ///
+    /// Forks are independent from each other, which makes them useful to preview
+    /// what would happen without affecting the original. Reactions added, removed
+    /// or disabled in a fork stay in that fork. History is not copied.
+    pub fn fork(&self) -> Essentia {
+        Essentia {
+            _private_ctor: (),
+            environment: self.environment.clone(),
+            heat_capacity: self.heat_capacity,
+            delta_time: self.delta_time,
+            is_in_equilibrium: self.is_in_equilibrium,
+            schedule: self.schedule.clone(),
+            heat_sources: self.heat_sources.clone(),
+            seed: self.seed,
+            substances: self.substances.clone(),
+            registry: Rc::clone(&self.registry),
+            reactions: Rc::clone(&self.reactions),
+            remainders: self.remainders.clone(),
+            history: self.history.as_ref().map(History::empty_like),
+            substance_counter: self.substance_counter.clone(),
+            is_agitated: self.is_agitated,
+            check_invariants: self.check_invariants,
+            invariant_violations: self.invariant_violations.clone(),
+            keep_reports: self.keep_reports,
+            reports: self.reports.clone(),
+        }
+    }
+
+    pub fn get_remainders(&self) -> &Remainders {
+        &self.remainders
+    }
+
+    pub(crate) fn next_substance_id(&self) -> SubstanceId {
+        // Ids are never reused, not even after the substance is gone
+        let id = self.substance_counter.get();
+        self.substance_counter
+            .set(id.checked_add(1).expect("Ran out of substance ids!"));
+        SubstanceId::from(id)
+    }
+
+    pub fn schedule(&mut self, at: Time, action: ScheduledAction) {
+        self.schedule.add(at, action);
     }
 
     pub fn add_substance(&mut self, substance: Substance) {
@@ -39,6 +103,10 @@ impl Essentia {
 }
 
 mod reactions;
+pub use reactions::{OrderingError, ReactionInfo};
+
+// Contains code applying catalysts and inhibitors to reaction products
+mod catalysis;
 
 // Contains engine simulation methods.
 mod simulation;
@@ -46,8 +114,28 @@ mod simulation;
 // Contains code for querying system's contents
 mod querying;
 
+// Contains the opt-in conservation checks run after each simulation
+mod invariants;
+pub use invariants::InvariantViolation;
+
+// Contains the per-tick report attributing products to their sources
+mod report;
+pub use report::{Contribution, Source, TickReport};
+
+// Contains the state checksum used to detect desyncs
+mod checksum;
+pub use checksum::StateDifference;
+
+// Contains the snapshot history used to rewind the simulation
+mod history;
+pub use history::RewindError;
+
+// Contains the configuration shared between forked engines
+mod registry;
+pub use registry::Registry;
+
 // Contains code to construct an instance of an engine
 mod builder;
 pub use builder::EssentiaBuilder;
 
-use self::reactions::ReactionLookup;
+use self::{history::History, reactions::ReactionLookup};
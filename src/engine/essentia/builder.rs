@@ -1,43 +1,78 @@
-use std::collections::HashMap;
+use std::{cell::Cell, collections::BTreeMap, rc::Rc};
 
 use crate::{
     abstractions::{reaction::Reaction, Essence, Form},
-    engine::builtin_reactions::{Dissolution, FormTransition, Precipitation},
+    engine::{
+        builtin_reactions::{Dissolution, FormTransition, Precipitation},
+        HeatSource, Remainders, Schedule,
+    },
     physics::{HeatCapacity, TimeSpan},
     Environment, EssenceId, FormId,
 };
 
-use super::{reactions::ReactionLookup, Essentia};
+use super::{
+    history::History,
+    reactions::{OrderingError, ReactionLookup},
+    Essentia, Registry,
+};
 
 pub struct EssentiaBuilder {
-    essence_lookup: HashMap<EssenceId, Essence>,
-    form_lookup: HashMap<FormId, Form>,
-    reactions: ReactionLookup,
+    essence_lookup: BTreeMap<EssenceId, Essence>,
+    form_lookup: BTreeMap<FormId, Form>,
+    reactions: Vec<Rc<dyn Reaction>>,
     starting_environment: Option<Environment>,
+    check_invariants: bool,
+    keep_reports: bool,
+    heat_sources: Vec<HeatSource>,
+    history: Option<History>,
+    seed: u64,
 }
 
 impl EssentiaBuilder {
     pub fn new() -> Self {
         EssentiaBuilder {
             starting_environment: Option::default(),
-            essence_lookup: HashMap::new(),
-            form_lookup: HashMap::new(),
-            reactions: ReactionLookup::new(),
+            essence_lookup: BTreeMap::new(),
+            form_lookup: BTreeMap::new(),
+            reactions: vec![],
+            check_invariants: false,
+            keep_reports: false,
+            heat_sources: vec![],
+            history: None,
+            seed: 0,
         }
     }
 
     pub fn build(self) -> Essentia {
-        Essentia {
+        self.try_build()
+            .unwrap_or_else(|error| panic!("Reactions can't be ordered: {error}!"))
+    }
+
+    // Fails when the reactions can't be ordered the way they ask to be
+    pub fn try_build(self) -> Result<Essentia, OrderingError> {
+        let reactions = ReactionLookup::new(self.reactions)?;
+
+        Ok(Essentia {
             _private_ctor: (),
             is_in_equilibrium: true,
+            schedule: Schedule::default(),
+            heat_sources: self.heat_sources,
+            seed: self.seed,
             heat_capacity: HeatCapacity::from(0),
             environment: self.starting_environment.unwrap_or_default(),
             delta_time: TimeSpan::from(0),
-            substances: HashMap::new(),
-            essence_lookup: self.essence_lookup,
-            form_lookup: self.form_lookup,
-            reactions: self.reactions,
-        }
+            substances: BTreeMap::new(),
+            registry: Rc::new(Registry::new(self.essence_lookup, self.form_lookup)),
+            reactions: Rc::new(reactions),
+            remainders: Remainders::default(),
+            history: self.history,
+            substance_counter: Cell::new(0),
+            is_agitated: false,
+            check_invariants: self.check_invariants,
+            invariant_violations: vec![],
+            keep_reports: self.keep_reports,
+            reports: vec![],
+        })
     }
 
     pub fn register_essence(mut self, essence: Essence) -> Self {
@@ -51,7 +86,40 @@ impl EssentiaBuilder {
     }
 
     pub fn register_reaction(mut self, reaction: Box<dyn Reaction>) -> Self {
-        self.reactions.insert(reaction);
+        self.reactions.push(Rc::from(reaction));
+        self
+    }
+
+    // Verifies conservation of matter and energy after every simulation tick.
+    // This is costly and is meant to be used in debug builds.
+    pub fn with_invariant_checks(mut self) -> Self {
+        self.check_invariants = true;
+        self
+    }
+
+    // Keeps a report of every simulation step, attributing the products to their sources.
+    // Meant for debugging, as it keeps every product emitted during the step.
+    pub fn with_reports(mut self) -> Self {
+        self.keep_reports = true;
+        self
+    }
+
+    // Keeps a snapshot every interval, up to the capacity, to be able to rewind the simulation
+    pub fn with_history(mut self, interval: TimeSpan, capacity: usize) -> Self {
+        if interval.ticks == 0 || capacity == 0 {
+            panic!("History needs a non-zero interval and capacity!");
+        }
+        self.history = Some(History::new(interval, capacity));
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_heat_source(mut self, heat_source: HeatSource) -> Self {
+        self.heat_sources.push(heat_source);
         self
     }
 }
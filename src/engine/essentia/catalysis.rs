@@ -0,0 +1,332 @@
+use crate::{
+    engine::ReactionContext,
+    physics::{Quantity, Ratio},
+    reaction::{Product, Reaction},
+};
+
+impl super::Essentia {
+    pub fn get_rate_multiplier(&self, reaction: &dyn Reaction) -> Ratio {
+        let mut multiplier = Ratio::one();
+        for catalyst in reaction.get_catalysts() {
+            let quantity = self.get_quantity_of(catalyst.essence_id, catalyst.form_id);
+            if catalyst.is_required && quantity == Quantity::none() {
+                return Ratio::zero();
+            }
+            multiplier = multiplier * catalyst.get_factor(quantity);
+        }
+
+        for inhibitor in reaction.get_inhibitors() {
+            let quantity = self.get_quantity_of(inhibitor.essence_id, inhibitor.form_id);
+            multiplier = multiplier / inhibitor.get_factor(quantity);
+        }
+
+        multiplier
+    }
+
+    pub(super) fn react_with_catalysis(
+        &self,
+        reaction: &dyn Reaction,
+        context: &ReactionContext,
+    ) -> Vec<Product> {
+        let catalysts = reaction.get_catalysts();
+        if catalysts.is_empty() && reaction.get_inhibitors().is_empty() {
+            return reaction.react(context);
+        }
+
+        let multiplier = self.get_rate_multiplier(reaction);
+        if multiplier.is_zero() {
+            return vec![];
+        }
+
+        let is_catalyst =
+            |essence_id, form_id| catalysts.iter().any(|c| c.matches(essence_id, form_id));
+
+        // Declarative reactions scale their own progress, limited by what exists
+        if reaction.as_declarative().is_some() {
+            context.set_rate_multiplier(multiplier);
+            let products = reaction.react(context);
+            context.set_rate_multiplier(Ratio::one());
+
+            // Catalysts are never used up by the reaction they speed up
+            return products
+                .into_iter()
+                .filter(|product| {
+                    !matches!(*product, Product::Consume(essence_id, form_id, _) if is_catalyst(essence_id, form_id))
+                })
+                .collect();
+        }
+
+        let products = reaction.react(context);
+        // Reaction has already limited itself to what exists, so speeding it up can't
+        // consume more than that. All products are scaled alike to keep them balanced
+        let multiplier = products
+            .iter()
+            .filter_map(|product| match *product {
+                Product::Consume(essence_id, form_id, quantity)
+                    if !is_catalyst(essence_id, form_id) =>
+                {
+                    let available = self.get_quantity_of(essence_id, form_id);
+                    Some(Ratio::new(available.mmol, quantity.mmol))
+                }
+                _ => None,
+            })
+            .fold(multiplier, Ratio::min);
+
+        products
+            .into_iter()
+            .filter_map(|product| match product {
+                Product::Consume(essence_id, form_id, _) if is_catalyst(essence_id, form_id) => {
+                    None
+                }
+                Product::Consume(essence_id, form_id, quantity) => {
+                    Some(Product::Consume(essence_id, form_id, quantity * multiplier))
+                }
+                Product::Produce(essence_id, form_id, quantity) => {
+                    Some(Product::Produce(essence_id, form_id, quantity * multiplier))
+                }
+                Product::Thermal(power) => Some(Product::Thermal(power * multiplier)),
+                _ => Some(product),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        engine::{Essentia, EssentiaBuilder, ReactionContext},
+        physics::{Energy, Power, Quantity, Rate, Ratio, TimeSpan},
+        reaction::{Catalyst, DeclarativeReactionBuilder, Product, Reaction, Reagent},
+        EssenceBuilder, EssenceId, Form, FormId, SubstanceBuilder,
+    };
+
+    enum Forms {
+        Solid = 1,
+    }
+
+    impl From<Forms> for FormId {
+        fn from(val: Forms) -> Self {
+            (val as u16).into()
+        }
+    }
+
+    enum Essences {
+        Ore = 1,
+        Philosopher = 2,
+        Lead = 3,
+    }
+
+    impl From<Essences> for EssenceId {
+        fn from(val: Essences) -> Self {
+            (val as u16).into()
+        }
+    }
+
+    struct Smelting {
+        catalysts: Vec<Catalyst>,
+        inhibitors: Vec<Catalyst>,
+    }
+
+    impl Reaction for Smelting {
+        fn react(&self, _context: &ReactionContext) -> Vec<Product> {
+            vec![
+                Product::Consume(
+                    Essences::Ore.into(),
+                    Forms::Solid.into(),
+                    Quantity::from(100),
+                ),
+                Product::Consume(
+                    Essences::Philosopher.into(),
+                    Forms::Solid.into(),
+                    Quantity::from(100),
+                ),
+                Product::Thermal(Power::from(100)),
+            ]
+        }
+
+        fn get_priority(&self) -> u8 {
+            100
+        }
+
+        fn get_catalysts(&self) -> &[Catalyst] {
+            &self.catalysts
+        }
+
+        fn get_inhibitors(&self) -> &[Catalyst] {
+            &self.inhibitors
+        }
+    }
+
+    fn build_engine() -> Essentia {
+        EssentiaBuilder::new()
+            .register_form(Form::new_with_id(Forms::Solid.into(), "Solid"))
+            .register_essence(
+                EssenceBuilder::default()
+                    .with_custom_id(Essences::Ore.into())
+                    .build(),
+            )
+            .register_essence(
+                EssenceBuilder::default()
+                    .with_custom_id(Essences::Philosopher.into())
+                    .build(),
+            )
+            .register_essence(
+                EssenceBuilder::default()
+                    .with_custom_id(Essences::Lead.into())
+                    .build(),
+            )
+            .build()
+    }
+
+    fn add(engine: &mut Essentia, essence: Essences, quantity: Quantity) {
+        let substance = SubstanceBuilder::new(engine)
+            .is_normal()
+            .with_essence(essence.into())
+            .with_form(Forms::Solid.into())
+            .with_quantity(quantity)
+            .build();
+        engine.add_substance(substance);
+    }
+
+    #[test]
+    fn catalyst_speeds_up_and_is_not_consumed() {
+        let mut engine = build_engine();
+        add(&mut engine, Essences::Philosopher, Quantity::from(2000));
+        add(&mut engine, Essences::Ore, Quantity::from(1000));
+        let reaction = Smelting {
+            catalysts: vec![Catalyst::new(
+                Essences::Philosopher.into(),
+                Forms::Solid.into(),
+                Ratio::new(1u64, 2u64),
+            )],
+            inhibitors: vec![],
+        };
+
+        let context = ReactionContext::new(&engine);
+        let products = engine.react_with_catalysis(&reaction, &context);
+
+        // 2 mol of catalyst at 0.5 potency doubles the rate
+        assert_eq!(
+            products,
+            vec![
+                Product::Consume(
+                    Essences::Ore.into(),
+                    Forms::Solid.into(),
+                    Quantity::from(200)
+                ),
+                Product::Thermal(Power::from(200)),
+            ]
+        );
+    }
+
+    #[test]
+    fn catalyst_doesnt_consume_more_than_exists() {
+        let mut engine = build_engine();
+        add(&mut engine, Essences::Philosopher, Quantity::from(4000));
+        add(&mut engine, Essences::Ore, Quantity::from(150));
+        let reaction = Smelting {
+            catalysts: vec![Catalyst::new(
+                Essences::Philosopher.into(),
+                Forms::Solid.into(),
+                Ratio::new(1u64, 2u64),
+            )],
+            inhibitors: vec![],
+        };
+
+        // Rate is tripled, but there is only enough ore for one and a half
+        let context = ReactionContext::new(&engine);
+        let products = engine.react_with_catalysis(&reaction, &context);
+        assert_eq!(
+            products,
+            vec![
+                Product::Consume(
+                    Essences::Ore.into(),
+                    Forms::Solid.into(),
+                    Quantity::from(150)
+                ),
+                Product::Thermal(Power::from(150)),
+            ]
+        );
+    }
+
+    #[test]
+    fn catalyst_keeps_declarative_reactions_balanced() {
+        let mut engine = build_engine();
+        add(&mut engine, Essences::Philosopher, Quantity::from(1000));
+        add(&mut engine, Essences::Ore, Quantity::from(1000));
+        engine.delta_time = TimeSpan::default();
+        let reaction = DeclarativeReactionBuilder::default()
+            .with_reactant(
+                Reagent::new(Essences::Ore.into(), Forms::Solid.into())
+                    .with_coefficient(2)
+                    .with_order(Ratio::zero()),
+            )
+            .with_product(Reagent::new(Essences::Lead.into(), Forms::Solid.into()))
+            .with_rate_constant(Rate::from(3))
+            .with_enthalpy(Energy::from(-1))
+            .with_catalyst(Catalyst::new(
+                Essences::Philosopher.into(),
+                Forms::Solid.into(),
+                Ratio::new(1u64, 2u64),
+            ))
+            .build();
+
+        // 1 mol of catalyst at 0.5 potency speeds the progress up to 4.5 mmol, of which 4 happen
+        let context = ReactionContext::new(&engine);
+        let products = engine.react_with_catalysis(&reaction, &context);
+        assert_eq!(
+            products,
+            vec![
+                Product::Consume(Essences::Ore.into(), Forms::Solid.into(), Quantity::from(8)),
+                Product::Produce(
+                    Essences::Lead.into(),
+                    Forms::Solid.into(),
+                    Quantity::from(4)
+                ),
+                Product::Thermal(Power::from(4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn required_catalyst_enables_reaction() {
+        let engine = build_engine();
+        let reaction = Smelting {
+            catalysts: vec![Catalyst::new(
+                Essences::Philosopher.into(),
+                Forms::Solid.into(),
+                Ratio::new(1u64, 2u64),
+            )
+            .required()],
+            inhibitors: vec![],
+        };
+
+        let context = ReactionContext::new(&engine);
+        assert!(engine.react_with_catalysis(&reaction, &context).is_empty());
+    }
+
+    #[test]
+    fn inhibitor_slows_down_reaction() {
+        let mut engine = build_engine();
+        add(&mut engine, Essences::Lead, Quantity::from(3000));
+        add(&mut engine, Essences::Ore, Quantity::from(1000));
+        add(&mut engine, Essences::Philosopher, Quantity::from(1000));
+        let reaction = Smelting {
+            catalysts: vec![],
+            inhibitors: vec![Catalyst::new(
+                Essences::Lead.into(),
+                Forms::Solid.into(),
+                Ratio::one(),
+            )],
+        };
+
+        assert_eq!(
+            engine.get_rate_multiplier(&reaction),
+            Ratio::new(1u64, 4u64)
+        );
+
+        let context = ReactionContext::new(&engine);
+        let products = engine.react_with_catalysis(&reaction, &context);
+        assert!(products.contains(&Product::Thermal(Power::from(25))));
+    }
+}
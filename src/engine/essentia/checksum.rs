@@ -0,0 +1,217 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    abstractions::SubstanceId,
+    engine::RemainderKey,
+    physics::{Pressure, Temperature, Time},
+    reaction::ReactionId,
+    Substance,
+};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+// 64-bit FNV-1a, fed with little-endian integers
+struct StateHasher {
+    hash: u64,
+}
+
+impl StateHasher {
+    fn new() -> Self {
+        StateHasher {
+            hash: FNV_OFFSET_BASIS,
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.hash ^= byte as u64;
+            self.hash = self.hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_u8(&mut self, value: u8) {
+        self.write(&[value]);
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_i64(&mut self, value: i64) {
+        self.write(&value.to_le_bytes());
+    }
+}
+
+/// Part of the state that differs between two engines.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateDifference {
+    Time(Time, Time),
+    Temperature(Temperature, Temperature),
+    Pressure(Pressure, Pressure),
+    Seed(u64, u64),
+    // Whether the system is agitated during the next simulation step
+    Agitated(bool, bool),
+    // Substance missing from one of the engines is None
+    Substance(SubstanceId, Option<Substance>, Option<Substance>),
+    Remainders,
+    // Whether the reaction is enabled, None if it is missing from one of the engines
+    Reaction(ReactionId, Option<bool>, Option<bool>),
+}
+
+impl super::Essentia {
+    /// Checksum of the simulation state, used to detect desyncs between engines
+    /// that are supposed to run in lockstep.
+    ///
+    /// The state is hashed with 64-bit FNV-1a, with every integer written in
+    /// little-endian byte order:
+    /// - time in ticks (`u64`), temperature in mK (`i64`), pressure in Pa (`u64`),
+    ///   the seed (`u64`) and `1` if the system is agitated or `0` if not (`u8`);
+    /// - number of substances (`u64`), then every substance in ascending order of ids:
+    ///   id (`u64`), `0` if free or `1` if a solution (`u8`), essence id (`u16`),
+    ///   form id (`u16`) and quantity in mmol (`u64`);
+    /// - for solutions, number of solutes (`u64`), then every solute in ascending
+    ///   order of essence ids: essence id (`u16`) and quantity in mmol (`u64`);
+    /// - number of rounding remainders (`u64`), then every remainder in ascending
+    ///   order of keys: kind (`u8`), essence id (`u16`), substance or form id (`u64`)
+    ///   and the remainder (`u64`), followed by the carried heat (`i64`);
+    /// - number of reactions (`u64`), then every reaction in ascending order of ids:
+    ///   id (`u64`) and `1` if enabled or `0` if disabled (`u8`).
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+
+        hasher.write_u64(self.environment.time.ticks);
+        hasher.write_i64(self.environment.temperature.mkelvin);
+        hasher.write_u64(self.environment.pressure.pascals);
+        hasher.write_u64(self.seed);
+        hasher.write_u8(self.is_agitated as u8);
+
+        hasher.write_u64(self.substances.len() as u64);
+        for (&id, substance) in &self.substances {
+            hasher.write_u64(id.into());
+            hasher.write_u8(matches!(substance, Substance::Solution(_, _, _)) as u8);
+            hasher.write_u16(substance.get_essence().into());
+            hasher.write_u16(substance.get_form().into());
+            hasher.write_u64(substance.get_quantity().mmol);
+
+            if let Substance::Solution(_, _, solutes) = substance {
+                hasher.write_u64(solutes.len() as u64);
+                for (&essence_id, quantity) in solutes {
+                    hasher.write_u16(essence_id.into());
+                    hasher.write_u64(quantity.mmol);
+                }
+            }
+        }
+
+        hasher.write_u64(self.remainders.iter().count() as u64);
+        for (&key, &remainder) in self.remainders.iter() {
+            let (kind, first, second) = match key {
+                RemainderKey::Dissolve(essence_id, substance_id) => {
+                    (0, essence_id.into(), substance_id.into())
+                }
+                RemainderKey::Precipitate(essence_id, substance_id) => {
+                    (1, essence_id.into(), substance_id.into())
+                }
+                RemainderKey::Transition(essence_id, form_id) => {
+                    (2, essence_id.into(), u16::from(form_id) as u64)
+                }
+            };
+            hasher.write_u8(kind);
+            hasher.write_u16(first);
+            hasher.write_u64(second);
+            hasher.write_u64(remainder);
+        }
+        hasher.write_i64(self.remainders.get_heat().joules);
+
+        hasher.write_u64(self.reactions.iter_enabled().count() as u64);
+        for (id, is_enabled) in self.reactions.iter_enabled() {
+            hasher.write_u64(id.into());
+            hasher.write_u8(is_enabled as u8);
+        }
+
+        hasher.hash
+    }
+
+    // Lists which parts of the state differ, to debug mismatching state hashes
+    pub fn diff_state(&self, other: &super::Essentia) -> Vec<StateDifference> {
+        let mut differences = vec![];
+        let (ours, theirs) = (&self.environment, &other.environment);
+
+        if ours.time != theirs.time {
+            differences.push(StateDifference::Time(ours.time, theirs.time));
+        }
+        if ours.temperature != theirs.temperature {
+            differences.push(StateDifference::Temperature(
+                ours.temperature,
+                theirs.temperature,
+            ));
+        }
+        if ours.pressure != theirs.pressure {
+            differences.push(StateDifference::Pressure(ours.pressure, theirs.pressure));
+        }
+        if self.seed != other.seed {
+            differences.push(StateDifference::Seed(self.seed, other.seed));
+        }
+        if self.is_agitated != other.is_agitated {
+            differences.push(StateDifference::Agitated(
+                self.is_agitated,
+                other.is_agitated,
+            ));
+        }
+
+        let ids = self
+            .substances
+            .keys()
+            .chain(other.substances.keys())
+            .copied()
+            .collect::<BTreeSet<_>>();
+        for id in ids {
+            let (ours, theirs) = (self.substances.get(&id), other.substances.get(&id));
+            if ours != theirs {
+                differences.push(StateDifference::Substance(
+                    id,
+                    ours.cloned(),
+                    theirs.cloned(),
+                ));
+            }
+        }
+
+        if self.remainders != other.remainders {
+            differences.push(StateDifference::Remainders);
+        }
+
+        let (ours, theirs) = (
+            self.reactions.iter_enabled().collect::<BTreeMap<_, _>>(),
+            other.reactions.iter_enabled().collect::<BTreeMap<_, _>>(),
+        );
+        let ids = ours.keys().chain(theirs.keys()).collect::<BTreeSet<_>>();
+        for &id in ids {
+            let (ours, theirs) = (ours.get(&id).copied(), theirs.get(&id).copied());
+            if ours != theirs {
+                differences.push(StateDifference::Reaction(id, ours, theirs));
+            }
+        }
+
+        differences
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::StateHasher;
+
+    #[test]
+    fn hasher_is_fnv_1a() {
+        let mut hasher = StateHasher::new();
+        hasher.write(b"a");
+        assert_eq!(hasher.hash, 0xaf63_dc4c_8601_ec8c);
+
+        let mut hasher = StateHasher::new();
+        hasher.write(b"foobar");
+        assert_eq!(hasher.hash, 0x8594_4171_f739_67e8);
+    }
+}
@@ -0,0 +1,273 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    error::Error,
+    fmt::Display,
+    rc::Rc,
+};
+
+use super::reactions::ReactionLookup;
+use crate::{
+    abstractions::{Environment, Substance, SubstanceId},
+    engine::{HeatSource, Remainders, Schedule, ScheduledAction},
+    physics::{Time, TimeSpan},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RewindError {
+    HistoryDisabled,
+    // Snapshots from that time have already been dropped from the history
+    TooOld,
+    // Time that hasn't been simulated yet
+    InFuture,
+}
+
+impl Display for RewindError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::HistoryDisabled => write!(f, "history is not enabled"),
+            Self::TooOld => write!(f, "time is older than the oldest snapshot"),
+            Self::InFuture => write!(f, "time hasn't been simulated yet"),
+        }
+    }
+}
+
+impl Error for RewindError {}
+
+// Everything done to the engine after a snapshot was taken, in order
+#[derive(Clone)]
+enum Entry {
+    Simulate(TimeSpan),
+    Perform(ScheduledAction),
+    // Reactions as they are after being changed
+    ChangeReactions(Rc<ReactionLookup>),
+}
+
+struct Snapshot {
+    environment: Environment,
+    substances: BTreeMap<SubstanceId, Substance>,
+    remainders: Remainders,
+    reactions: Rc<ReactionLookup>,
+    schedule: Schedule,
+    heat_sources: Vec<HeatSource>,
+    seed: u64,
+    substance_counter: u64,
+    is_agitated: bool,
+    is_in_equilibrium: bool,
+    entries: Vec<Entry>,
+}
+
+/// Ring buffer of snapshots taken every `interval`, along with everything
+/// done to the engine since, so that any moment in between can be replayed.
+pub(super) struct History {
+    interval: TimeSpan,
+    capacity: usize,
+    snapshots: VecDeque<Snapshot>,
+    // Simulated time is known up to this moment
+    recorded_until: Time,
+    // Hash of the state as recorded, to notice changes made behind history's back
+    recorded_hash: u64,
+    // Schedule and heat sources are public as well, but aren't a part of the state hash
+    recorded_schedule: Schedule,
+    recorded_heat_sources: Vec<HeatSource>,
+    // Set while the engine is rewound to the past: (snapshot, entry, ticks into that entry)
+    cursor: Option<(usize, usize, u64)>,
+    is_replaying: bool,
+}
+
+impl History {
+    pub(super) fn new(interval: TimeSpan, capacity: usize) -> Self {
+        History {
+            interval,
+            capacity,
+            snapshots: VecDeque::new(),
+            recorded_until: Time::new(),
+            recorded_hash: 0,
+            recorded_schedule: Schedule::default(),
+            recorded_heat_sources: vec![],
+            cursor: None,
+            is_replaying: false,
+        }
+    }
+
+    // Same configuration, but nothing recorded yet
+    pub(super) fn empty_like(&self) -> Self {
+        History::new(self.interval, self.capacity)
+    }
+
+    // Drops the rewound future, as soon as something different happens in the past
+    fn discard_future(&mut self) {
+        if let Some((snapshot, entry, ticks)) = self.cursor.take() {
+            self.snapshots.truncate(snapshot + 1);
+            let entries = &mut self.snapshots[snapshot].entries;
+            entries.truncate(entry);
+            if ticks > 0 {
+                entries.push(Entry::Simulate(TimeSpan::from(ticks)));
+            }
+        }
+    }
+
+    fn record(&mut self, entry: Entry) {
+        if let Some(snapshot) = self.snapshots.back_mut() {
+            snapshot.entries.push(entry);
+        }
+    }
+}
+
+impl super::Essentia {
+    // Called before simulating, takes a snapshot when one is due
+    pub(super) fn record_simulation(&mut self, delta_time: TimeSpan) {
+        if !self.is_recording() {
+            return;
+        }
+        let state_hash = self.state_hash();
+        let history = self.history.as_mut().unwrap();
+        history.discard_future();
+
+        let is_due = history.snapshots.back().is_none_or(|snapshot| {
+            self.environment.time.ticks >= snapshot.environment.time.ticks + history.interval.ticks
+        });
+        let is_changed = state_hash != history.recorded_hash
+            || self.schedule != history.recorded_schedule
+            || self.heat_sources != history.recorded_heat_sources;
+        if is_due || is_changed {
+            self.take_snapshot();
+        }
+
+        let history = self.history.as_mut().unwrap();
+        history.record(Entry::Simulate(delta_time));
+    }
+
+    // Called after simulating or performing an action
+    pub(super) fn finish_recording(&mut self) {
+        if !self.is_recording() {
+            return;
+        }
+        self.finish_recording_at(self.environment.time);
+    }
+
+    fn finish_recording_at(&mut self, recorded_until: Time) {
+        let state_hash = self.state_hash();
+        let history = self.history.as_mut().unwrap();
+        history.recorded_until = recorded_until;
+        history.recorded_hash = state_hash;
+        history.recorded_schedule = self.schedule.clone();
+        history.recorded_heat_sources = self.heat_sources.clone();
+    }
+
+    fn is_recording(&self) -> bool {
+        self.history
+            .as_ref()
+            .is_some_and(|history| !history.is_replaying)
+    }
+
+    pub(super) fn record_action(&mut self, action: ScheduledAction) {
+        if let Some(history) = self.history.as_mut().filter(|h| !h.is_replaying) {
+            history.discard_future();
+            history.record(Entry::Perform(action));
+        }
+    }
+
+    pub(super) fn record_reactions(&mut self) {
+        let reactions = Rc::clone(&self.reactions);
+        if let Some(history) = self.history.as_mut().filter(|h| !h.is_replaying) {
+            history.discard_future();
+            history.record(Entry::ChangeReactions(reactions));
+        }
+        self.finish_recording();
+    }
+
+    fn take_snapshot(&mut self) {
+        let snapshot = Snapshot {
+            environment: self.environment.clone(),
+            substances: self.substances.clone(),
+            remainders: self.remainders.clone(),
+            reactions: Rc::clone(&self.reactions),
+            schedule: self.schedule.clone(),
+            heat_sources: self.heat_sources.clone(),
+            seed: self.seed,
+            substance_counter: self.substance_counter.get(),
+            is_agitated: self.is_agitated,
+            is_in_equilibrium: self.is_in_equilibrium,
+            entries: vec![],
+        };
+
+        let history = self.history.as_mut().unwrap();
+        if history.snapshots.len() == history.capacity {
+            history.snapshots.pop_front();
+        }
+        history.snapshots.push_back(snapshot);
+    }
+
+    fn restore_snapshot(&mut self, index: usize) {
+        let snapshot = &self.history.as_ref().unwrap().snapshots[index];
+
+        self.environment = snapshot.environment.clone();
+        self.substances = snapshot.substances.clone();
+        self.remainders = snapshot.remainders.clone();
+        self.reactions = Rc::clone(&snapshot.reactions);
+        self.schedule = snapshot.schedule.clone();
+        self.heat_sources = snapshot.heat_sources.clone();
+        self.seed = snapshot.seed;
+        self.substance_counter.set(snapshot.substance_counter);
+        self.is_agitated = snapshot.is_agitated;
+        self.is_in_equilibrium = snapshot.is_in_equilibrium;
+    }
+
+    /// Brings the engine back to the given moment, by restoring the latest
+    /// snapshot before it and simulating forward to the exact tick.
+    ///
+    /// The history after that moment is kept until something else is simulated
+    /// or performed, so it is possible to scrub forward again.
+    pub fn rewind_to(&mut self, time: Time) -> Result<(), RewindError> {
+        let history = self.history.as_ref().ok_or(RewindError::HistoryDisabled)?;
+        if time.ticks > history.recorded_until.ticks {
+            return Err(RewindError::InFuture);
+        }
+        let index = history
+            .snapshots
+            .iter()
+            .rposition(|snapshot| snapshot.environment.time.ticks <= time.ticks)
+            .ok_or(RewindError::TooOld)?;
+        let entries = history.snapshots[index].entries.clone();
+
+        self.restore_snapshot(index);
+        self.history.as_mut().unwrap().is_replaying = true;
+
+        // Actions performed right at that moment are replayed as well
+        let mut cursor = (index, entries.len(), 0);
+        for (entry_index, entry) in entries.into_iter().enumerate() {
+            match entry {
+                Entry::Perform(action) => self.perform(action),
+                Entry::ChangeReactions(reactions) => self.reactions = reactions,
+                Entry::Simulate(delta_time) => {
+                    let remaining = time.ticks - self.environment.time.ticks;
+                    if remaining == 0 {
+                        cursor = (index, entry_index, 0);
+                        break;
+                    }
+                    if delta_time.ticks > remaining {
+                        self.simulate(TimeSpan::from(remaining));
+                        cursor = (index, entry_index, remaining);
+                        break;
+                    }
+                    self.simulate(delta_time);
+                }
+            }
+        }
+
+        let recorded_until = self.history.as_ref().unwrap().recorded_until;
+        self.finish_recording_at(recorded_until);
+        let history = self.history.as_mut().unwrap();
+        history.is_replaying = false;
+        history.cursor = Some(cursor);
+        Ok(())
+    }
+
+    // Earliest moment the engine can be rewound to
+    pub fn get_history_start(&self) -> Option<Time> {
+        self.history
+            .as_ref()
+            .and_then(|history| history.snapshots.front())
+            .map(|snapshot| snapshot.environment.time)
+    }
+}
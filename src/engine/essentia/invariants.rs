@@ -0,0 +1,435 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    engine::{builtin_reactions::FormTransition, Contribution, Source},
+    physics::{Energy, HeatCapacity, Power, Quantity, Temperature, TimeSpan},
+    reaction::Product,
+    EssenceId, Substance,
+};
+
+/// Broken conservation law detected after a simulation tick.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InvariantViolation {
+    // Total quantity of the essence doesn't match the net of applied products
+    Matter {
+        essence_id: EssenceId,
+        expected_mmol: i128,
+        actual: Quantity,
+        product: Option<Product>,
+    },
+    // Heat gained by the system, counting the heat carried over to the next tick,
+    // doesn't match the heat from applied products
+    Energy {
+        expected: Energy,
+        actual: Energy,
+        product: Option<Product>,
+    },
+    // Heat used by the form transitions doesn't match the latent heat of what transitioned
+    LatentHeat {
+        expected: Energy,
+        actual: Energy,
+        product: Option<Product>,
+    },
+}
+
+pub(super) struct InvariantSnapshot {
+    essence_totals: BTreeMap<EssenceId, Quantity>,
+    temperature: Temperature,
+    heat_capacity: HeatCapacity,
+    // Heat carried over from earlier ticks, applied together with the products
+    heat: Energy,
+    transition_heat: Option<TransitionHeat>,
+}
+
+// Heat emitted by the form transitions, against the latent heat of what they transitioned
+struct TransitionHeat {
+    emitted: Energy,
+    power: Power,
+    latent: Energy,
+    // Split quantities are rounded to whole mmol and the rest is carried over,
+    // so every conversion may be off by a mmol either way
+    tolerance: Energy,
+}
+
+fn to_energy(joules: i128) -> Energy {
+    Energy::from(joules.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+}
+
+impl InvariantSnapshot {
+    pub(super) fn verify(
+        &self,
+        engine: &super::Essentia,
+        products: &[Product],
+        delta_time: TimeSpan,
+    ) -> Vec<InvariantViolation> {
+        let mut violations = vec![];
+
+        let mut net_change = BTreeMap::<EssenceId, i128>::new();
+        for product in products {
+            match *product {
+                Product::Produce(essence_id, _, quantity) => {
+                    *net_change.entry(essence_id).or_default() += quantity.mmol as i128;
+                }
+                Product::Consume(essence_id, _, quantity) => {
+                    *net_change.entry(essence_id).or_default() -= quantity.mmol as i128;
+                }
+                // Dissolving and precipitating only moves matter around
+                _ => {}
+            }
+        }
+
+        let actual_totals = engine.get_essence_totals();
+        let mut essence_ids = self
+            .essence_totals
+            .keys()
+            .chain(actual_totals.keys())
+            .chain(net_change.keys())
+            .copied()
+            .collect::<Vec<_>>();
+        essence_ids.sort_by_key(|&id| u16::from(id));
+        essence_ids.dedup();
+
+        for essence_id in essence_ids {
+            let before = self
+                .essence_totals
+                .get(&essence_id)
+                .copied()
+                .unwrap_or(Quantity::none());
+            let actual = actual_totals
+                .get(&essence_id)
+                .copied()
+                .unwrap_or(Quantity::none());
+            let expected_mmol =
+                before.mmol as i128 + net_change.get(&essence_id).copied().unwrap_or_default();
+
+            if expected_mmol != actual.mmol as i128 {
+                violations.push(InvariantViolation::Matter {
+                    essence_id,
+                    expected_mmol,
+                    actual,
+                    product: Self::find_offending_product(products, essence_id),
+                });
+            }
+        }
+
+        let thermal = products
+            .iter()
+            .find(|p| matches!(p, Product::Thermal(_)))
+            .copied();
+        let applied = match thermal {
+            Some(Product::Thermal(power)) => power * delta_time,
+            _ => Energy::from(0),
+        };
+
+        // Without anything to hold it, there is no heat to conserve
+        let capacity = self.heat_capacity.joule_per_mkelvin as i128;
+        if capacity != 0 {
+            let expected = applied + self.heat;
+            let temperature_change =
+                engine.environment.temperature.mkelvin as i128 - self.temperature.mkelvin as i128;
+            let actual = to_energy(
+                temperature_change * capacity + engine.get_remainders().get_heat().joules as i128,
+            );
+            if expected != actual {
+                violations.push(InvariantViolation::Energy {
+                    expected,
+                    actual,
+                    product: thermal,
+                });
+            }
+        }
+
+        if let Some(heat) = &self.transition_heat {
+            let expected = Energy::from(0) - heat.latent;
+            if (heat.emitted.joules as i128 - expected.joules as i128).abs()
+                > heat.tolerance.joules as i128
+            {
+                violations.push(InvariantViolation::LatentHeat {
+                    expected,
+                    actual: heat.emitted,
+                    product: Some(Product::Thermal(heat.power)),
+                });
+            }
+        }
+
+        violations
+    }
+
+    // Consumption is the usual suspect, since it can ask for more than exists
+    fn find_offending_product(products: &[Product], essence_id: EssenceId) -> Option<Product> {
+        let touches_essence = |product: &&Product| match product {
+            Product::Produce(id, _, _) | Product::Consume(id, _, _) => *id == essence_id,
+            Product::Dissolve(id, _, _, _) | Product::Precipitate(id, _, _, _) => *id == essence_id,
+            Product::Thermal(_) => false,
+        };
+
+        products
+            .iter()
+            .filter(touches_essence)
+            .find(|p| matches!(p, Product::Consume(_, _, _)))
+            .or_else(|| products.iter().find(touches_essence))
+            .copied()
+    }
+}
+
+impl super::Essentia {
+    // Total quantity of every essence, whether free, solution base or dissolved
+    pub fn get_essence_totals(&self) -> BTreeMap<EssenceId, Quantity> {
+        let mut totals = BTreeMap::<EssenceId, Quantity>::new();
+        for substance in self.iter_all() {
+            *totals
+                .entry(substance.get_essence())
+                .or_insert(Quantity::none()) += substance.get_quantity();
+
+            if let Substance::Solution(_, _, solutes) = substance {
+                for (&essence_id, &quantity) in solutes {
+                    *totals.entry(essence_id).or_insert(Quantity::none()) += quantity;
+                }
+            }
+        }
+
+        totals
+    }
+
+    pub fn get_invariant_violations(&self) -> &[InvariantViolation] {
+        &self.invariant_violations
+    }
+
+    pub(super) fn take_invariant_snapshot(
+        &self,
+        contributions: &[Contribution],
+        carried_heat: &BTreeMap<Source, Energy>,
+    ) -> InvariantSnapshot {
+        InvariantSnapshot {
+            essence_totals: self.get_essence_totals(),
+            temperature: self.environment.temperature,
+            heat_capacity: self.heat_capacity,
+            heat: self.remainders.get_heat(),
+            transition_heat: self.get_transition_heat(contributions, carried_heat),
+        }
+    }
+
+    // Form transitions only turn sensible heat into latent heat and back
+    fn get_transition_heat(
+        &self,
+        contributions: &[Contribution],
+        carried_heat: &BTreeMap<Source, Energy>,
+    ) -> Option<TransitionHeat> {
+        let source = Source::Reaction(self.find_reaction(FormTransition::NAME)?);
+        let mut heat = TransitionHeat {
+            emitted: carried_heat
+                .get(&source)
+                .copied()
+                .unwrap_or(Energy::from(0)),
+            power: Power::from(0),
+            latent: Energy::from(0),
+            tolerance: Energy::from(0),
+        };
+
+        // Every transitioned quantity is consumed in one form and produced in the other
+        let mut consumed = None;
+        for contribution in contributions.iter().filter(|c| c.source == source) {
+            match contribution.product {
+                Product::Thermal(power) => {
+                    heat.power = heat.power + power;
+                    heat.emitted += power * self.delta_time;
+                }
+                Product::Consume(essence_id, form_id, _) => consumed = Some((essence_id, form_id)),
+                Product::Produce(essence_id, to, quantity) => {
+                    let Some((_, from)) = consumed.take().filter(|(id, _)| *id == essence_id)
+                    else {
+                        continue;
+                    };
+                    let Some(graph) = self
+                        .get_essence(essence_id)
+                        .and_then(|essence| essence.phase_graph.as_ref())
+                    else {
+                        continue;
+                    };
+
+                    for transition in graph.iter_transitions() {
+                        let latent = transition.joules_per_mol * quantity.mmol;
+                        if (transition.left_form_id, transition.right_form_id) == (from, to) {
+                            heat.latent += latent;
+                        } else if (transition.left_form_id, transition.right_form_id) == (to, from)
+                        {
+                            heat.latent -= latent;
+                        } else {
+                            continue;
+                        }
+                        heat.tolerance += transition.joules_per_mol;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Some(heat)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::InvariantViolation;
+    use crate::{
+        engine::{
+            builtin_reactions::FormTransition, Essentia, EssentiaBuilder, HeatSource,
+            ReactionContext,
+        },
+        physics::{Energy, PhaseTransition, Power, Quantity, Temperature, TimeSpan},
+        reaction::{Product, Reaction, Stage},
+        EssenceBuilder, EssenceId, Form, FormId, SubstanceBuilder,
+    };
+
+    struct Devour {
+        quantity: Quantity,
+    }
+
+    impl Reaction for Devour {
+        fn react(&self, _context: &ReactionContext) -> Vec<Product> {
+            vec![
+                Product::Consume(0.into(), 0.into(), self.quantity),
+                Product::Thermal(Power::from(100)),
+            ]
+        }
+
+        fn get_priority(&self) -> u8 {
+            0
+        }
+    }
+
+    // Melts without paying for it
+    struct FreeMelting;
+
+    impl Reaction for FreeMelting {
+        fn react(&self, _context: &ReactionContext) -> Vec<Product> {
+            vec![
+                Product::Consume(0.into(), 0.into(), Quantity::from(100)),
+                Product::Produce(0.into(), 1.into(), Quantity::from(100)),
+            ]
+        }
+
+        fn get_stage(&self) -> Stage {
+            Stage::Transition
+        }
+
+        fn get_name(&self) -> Option<&str> {
+            Some(FormTransition::NAME)
+        }
+    }
+
+    fn build_melting_engine(transition: Box<dyn Reaction>) -> Essentia {
+        let mut engine = EssentiaBuilder::new()
+            .register_form(Form::new_with_id(FormId::from(0), "Solid"))
+            .register_form(Form::new_with_id(FormId::from(1), "Liquid"))
+            .register_essence(
+                EssenceBuilder::default()
+                    .with_custom_id(EssenceId::from(0))
+                    .with_phase_transitions(|builder| {
+                        builder.add_transition(PhaseTransition {
+                            threshold: Temperature::from(273_000),
+                            joules_per_mol: Energy::from(3),
+                            left_form_id: 0.into(),
+                            right_form_id: 1.into(),
+                            pressure_curve: None,
+                            hysteresis: None,
+                        });
+                    })
+                    .build(),
+            )
+            .register_reaction(transition)
+            .with_heat_source(HeatSource::new(Power::from(1000)))
+            .with_invariant_checks()
+            .build();
+        engine.environment.temperature = Temperature::from(272_999);
+
+        let substance = SubstanceBuilder::new(&engine)
+            .is_normal()
+            .with_essence(0.into())
+            .with_form(0.into())
+            .with_quantity(Quantity::from(1000))
+            .build();
+        engine.add_substance(substance);
+        engine
+    }
+
+    fn build_engine(quantity: Quantity) -> Essentia {
+        let mut engine = EssentiaBuilder::new()
+            .register_form(Form::new_with_id(FormId::from(0), "Solid"))
+            .register_essence(
+                EssenceBuilder::default()
+                    .with_custom_id(EssenceId::from(0))
+                    .build(),
+            )
+            .register_reaction(Box::new(Devour { quantity }))
+            .with_invariant_checks()
+            .build();
+
+        let substance = SubstanceBuilder::new(&engine)
+            .is_normal()
+            .with_essence(0.into())
+            .with_form(0.into())
+            .with_quantity(Quantity::from(1000))
+            .build();
+        engine.add_substance(substance);
+        engine
+    }
+
+    #[test]
+    fn no_violations_when_products_are_applied_exactly() {
+        let mut engine = build_engine(Quantity::from(400));
+
+        engine.simulate(TimeSpan::default());
+
+        assert!(engine.get_invariant_violations().is_empty());
+    }
+
+    #[test]
+    fn reports_consuming_more_than_exists() {
+        let mut engine = build_engine(Quantity::from(1500));
+
+        engine.simulate(TimeSpan::default());
+
+        assert_eq!(
+            engine.get_invariant_violations(),
+            &[InvariantViolation::Matter {
+                essence_id: 0.into(),
+                expected_mmol: -500,
+                actual: Quantity::none(),
+                product: Some(Product::Consume(0.into(), 0.into(), Quantity::from(1500))),
+            }]
+        );
+    }
+
+    #[test]
+    fn transitions_pay_their_latent_heat() {
+        let mut engine = build_melting_engine(Box::new(FormTransition));
+
+        // 3000 J per tick: 1000 J to reach melting, then 2000 J out of 3000 J to melt it all
+        engine.simulate(TimeSpan::from(3));
+        assert!(engine.get_invariant_violations().is_empty());
+        assert_eq!(
+            engine.get_quantity_of(0.into(), 1.into()),
+            Quantity::from(666)
+        );
+
+        engine.simulate(TimeSpan::from(3));
+        assert!(engine.get_invariant_violations().is_empty());
+    }
+
+    #[test]
+    fn reports_transitions_that_dont_pay_latent_heat() {
+        let mut engine = build_melting_engine(Box::new(FreeMelting));
+
+        engine.simulate(TimeSpan::from(1));
+
+        assert_eq!(
+            engine.get_invariant_violations(),
+            &[InvariantViolation::LatentHeat {
+                expected: Energy::from(-300),
+                actual: Energy::from(0),
+                product: Some(Product::Thermal(Power::from(0))),
+            }]
+        );
+    }
+}
@@ -1,7 +1,8 @@
 use crate::{
     abstractions::SubstanceId,
-    physics::{PhaseGraph, Solubility},
-    EssenceId, Substance,
+    physics::{PhaseGraph, Quantity, Solubility},
+    reaction::Reaction,
+    Essence, EssenceId, FormId, Substance,
 };
 
 impl super::Essentia {
@@ -55,8 +56,19 @@ impl super::Essentia {
         })
     }
 
+    pub fn iter_essences(&self) -> impl Iterator<Item = &Essence> {
+        self.registry.iter_essences()
+    }
+
+    // Reactions in the order they are applied in
+    pub fn iter_reactions(&self) -> impl Iterator<Item = &dyn Reaction> {
+        self.reactions
+            .iter_groups()
+            .flat_map(|group| group.map(|(_, reaction)| reaction))
+    }
+
     pub fn get_solubility(&self, essence_id: EssenceId) -> Option<Solubility> {
-        self.essence_lookup.get(&essence_id).map(|x| x.solubility)?
+        self.get_essence(essence_id).and_then(|x| x.solubility)
     }
 
     pub fn get_with_phase_graphs(&self) -> impl Iterator<Item = (&Substance, &PhaseGraph)> {
@@ -72,4 +84,12 @@ impl super::Essentia {
     pub fn get_substance(&self, substance_id: SubstanceId) -> Option<&Substance> {
         self.substances.get(&substance_id)
     }
+
+    pub fn get_quantity_of(&self, essence_id: EssenceId, form_id: FormId) -> Quantity {
+        self.substances
+            .values()
+            .filter(|substance| substance.is_essence(essence_id) && substance.is_form(form_id))
+            .map(|substance| substance.get_quantity())
+            .sum()
+    }
 }
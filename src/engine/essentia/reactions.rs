@@ -1,54 +1,414 @@
-use crate::reaction::Reaction;
+use std::{collections::BTreeMap, error::Error, fmt::Display, rc::Rc};
 
-pub struct ReactionGroup {
-    reactions: Vec<Box<dyn Reaction>>,
-}
+use crate::reaction::{Reaction, ReactionId, Stage};
 
-pub struct ReactionLookup {
-    reaction_map: Vec<(u8, ReactionGroup)>,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderingError {
+    // Two reactions registered under the same name
+    DuplicateName(String),
+    // Reaction has to run before a reaction of an earlier stage, or after one of a later stage
+    StageConflict(String, String),
+    // Names of the reactions that have to run after each other, in a loop
+    Cycle(Vec<String>),
 }
 
-impl ReactionGroup {
-    pub fn push(&mut self, reaction: Box<dyn Reaction>) {
-        self.reactions.push(reaction);
+impl Display for OrderingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DuplicateName(name) => write!(f, "reaction {name} is registered twice"),
+            Self::StageConflict(reaction, other) => write!(
+                f,
+                "reaction {reaction} can't be ordered around {other}, which runs in another stage"
+            ),
+            Self::Cycle(names) => write!(f, "reactions {} run after each other", names.join(", ")),
+        }
     }
+}
+
+impl Error for OrderingError {}
 
-    pub fn iter_reactions(&self) -> impl Iterator<Item = &Box<dyn Reaction>> {
-        self.reactions.iter()
+/// Registered reaction as seen from the outside.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReactionInfo {
+    pub id: ReactionId,
+    pub name: Option<String>,
+    pub stage: Stage,
+    pub priority: u8,
+    pub is_enabled: bool,
+}
+
+#[derive(Clone)]
+struct Registered {
+    id: ReactionId,
+    reaction: Rc<dyn Reaction>,
+    is_enabled: bool,
+}
+
+impl Registered {
+    fn describe(&self) -> String {
+        self.reaction
+            .get_name()
+            .map(String::from)
+            .unwrap_or_else(|| format!("#{}", u64::from(self.id)))
     }
 }
 
+/// Reactions grouped in the order they run in.
+///
+/// Reactions run stage by stage. Within a stage, a reaction that has to run
+/// after another one is moved to a later group, behind everything that doesn't
+/// wait for anything. Otherwise they run in the order of their priority, and in
+/// the order they were registered within the same priority. Reactions with the
+/// same stage, priority and position see the same context.
+#[derive(Clone)]
+pub struct ReactionLookup {
+    registered: Vec<Registered>,
+    // Indices into the registered reactions
+    groups: Vec<Vec<usize>>,
+    id_counter: u64,
+}
+
 impl ReactionLookup {
-    pub fn new() -> Self {
-        ReactionLookup {
-            reaction_map: vec![],
+    pub fn new(reactions: Vec<Rc<dyn Reaction>>) -> Result<Self, OrderingError> {
+        let mut lookup = ReactionLookup {
+            registered: vec![],
+            groups: vec![],
+            id_counter: 0,
+        };
+        for reaction in reactions {
+            let id = lookup.next_id();
+            lookup.registered.push(Registered {
+                id,
+                reaction,
+                is_enabled: true,
+            });
+        }
+        lookup.groups = Self::order(&lookup.registered)?;
+        Ok(lookup)
+    }
+
+    fn next_id(&mut self) -> ReactionId {
+        let id = self.id_counter;
+        self.id_counter = id.checked_add(1).expect("Ran out of reaction ids!");
+        id.into()
+    }
+
+    // Reaction is left out when it can't be ordered the way it asks to be
+    pub fn insert(&mut self, reaction: Rc<dyn Reaction>) -> Result<ReactionId, OrderingError> {
+        self.registered.push(Registered {
+            id: self.id_counter.into(),
+            reaction,
+            is_enabled: true,
+        });
+
+        match Self::order(&self.registered) {
+            Ok(groups) => {
+                self.groups = groups;
+                Ok(self.next_id())
+            }
+            Err(error) => {
+                self.registered.pop();
+                Err(error)
+            }
         }
     }
 
-    pub fn insert(&mut self, reaction: Box<dyn Reaction>) {
-        let inserted_prio = reaction.get_priority();
-        let existing_entry = self
-            .reaction_map
+    pub fn remove(&mut self, id: ReactionId) -> Option<Rc<dyn Reaction>> {
+        let index = self.registered.iter().position(|entry| entry.id == id)?;
+        let removed = self.registered.remove(index);
+        // Relationships to the removed reaction are ignored from now on
+        self.groups =
+            Self::order(&self.registered).expect("Removing a reaction can't break the order!");
+        Some(removed.reaction)
+    }
+
+    // Disabled reactions keep their place in the order, but don't run
+    pub fn set_enabled(&mut self, id: ReactionId, is_enabled: bool) -> bool {
+        self.registered
             .iter_mut()
-            .find(|(prio, _)| *prio == inserted_prio);
-
-        if let Some((_, reactions_in_group)) = existing_entry {
-            reactions_in_group.push(reaction);
-        } else {
-            self.reaction_map.push((
-                inserted_prio,
-                ReactionGroup {
-                    reactions: vec![reaction],
-                },
-            ));
-            // Keep the lookup sorted on insert.
-            // This is fine since it only happens in setup of the builder
-            self.reaction_map
-                .sort_by(|(prio_a, _), (prio_b, _)| prio_a.cmp(prio_b));
-        }
-    }
-
-    pub fn iter_groups(&self) -> impl Iterator<Item = &ReactionGroup> {
-        self.reaction_map.iter().map(|(_, group)| group)
+            .find(|entry| entry.id == id)
+            .map(|entry| entry.is_enabled = is_enabled)
+            .is_some()
+    }
+
+    fn order(registered: &[Registered]) -> Result<Vec<Vec<usize>>, OrderingError> {
+        let mut names = BTreeMap::new();
+        for (index, entry) in registered.iter().enumerate() {
+            if let Some(name) = entry.reaction.get_name() {
+                if names.insert(name, index).is_some() {
+                    return Err(OrderingError::DuplicateName(String::from(name)));
+                }
+            }
+        }
+
+        // Edges from a reaction to the ones that wait for it, only within a stage.
+        // Names of reactions that aren't registered are ignored
+        let mut waiting = vec![vec![]; registered.len()];
+        for (index, entry) in registered.iter().enumerate() {
+            let stage = entry.reaction.get_stage();
+            let after = entry
+                .reaction
+                .get_run_after()
+                .into_iter()
+                .map(|name| (name, true));
+            let before = entry
+                .reaction
+                .get_run_before()
+                .into_iter()
+                .map(|name| (name, false));
+
+            for (name, is_after) in after.chain(before) {
+                let Some(&other) = names.get(name) else {
+                    continue;
+                };
+                let other_stage = registered[other].reaction.get_stage();
+                let (first, second) = if is_after {
+                    (other, index)
+                } else {
+                    (index, other)
+                };
+
+                if stage == other_stage {
+                    waiting[first].push(second);
+                } else if (other_stage > stage) == is_after {
+                    return Err(OrderingError::StageConflict(
+                        entry.describe(),
+                        String::from(name),
+                    ));
+                }
+            }
+        }
+
+        // Longest chain of reactions each one waits for, in topological order
+        let mut waits_for = vec![0; registered.len()];
+        for next in waiting.iter().flatten() {
+            waits_for[*next] += 1;
+        }
+        let mut depths = vec![0; registered.len()];
+        let mut ready = (0..registered.len())
+            .filter(|&index| waits_for[index] == 0)
+            .collect::<Vec<_>>();
+        let mut visited = 0;
+        while let Some(index) = ready.pop() {
+            visited += 1;
+            for &next in &waiting[index] {
+                depths[next] = depths[next].max(depths[index] + 1);
+                waits_for[next] -= 1;
+                if waits_for[next] == 0 {
+                    ready.push(next);
+                }
+            }
+        }
+
+        if visited < registered.len() {
+            return Err(OrderingError::Cycle(Self::find_cycle(
+                registered, &waiting, &waits_for,
+            )));
+        }
+
+        let mut groups = BTreeMap::<_, Vec<_>>::new();
+        for (index, entry) in registered.iter().enumerate() {
+            let reaction = &entry.reaction;
+            let key = (reaction.get_stage(), depths[index], reaction.get_priority());
+            groups.entry(key).or_default().push(index);
+        }
+
+        Ok(groups.into_values().collect())
+    }
+
+    // Walks back over the reactions left out of the topological order until one repeats.
+    // Each of them still waits for another one left out, while a reaction that only
+    // sits downstream of a cycle doesn't necessarily lead anywhere
+    fn find_cycle(
+        registered: &[Registered],
+        waiting: &[Vec<usize>],
+        waits_for: &[usize],
+    ) -> Vec<String> {
+        let mut path = vec![];
+        let mut current = (0..registered.len())
+            .find(|&index| waits_for[index] > 0)
+            .expect("Cycle must leave some reactions out of the order!");
+
+        while !path.contains(&current) {
+            path.push(current);
+            current = (0..registered.len())
+                .find(|&previous| waits_for[previous] > 0 && waiting[previous].contains(&current))
+                .expect("Reaction left out of the order must wait for another one!");
+        }
+
+        let start = path.iter().position(|&index| index == current).unwrap();
+        path[start..]
+            .iter()
+            .rev()
+            .map(|&index| registered[index].describe())
+            .collect()
+    }
+
+    // Enabled reactions, grouped in the order they run in
+    pub fn iter_groups(
+        &self,
+    ) -> impl Iterator<Item = impl Iterator<Item = (ReactionId, &dyn Reaction)>> {
+        self.groups.iter().map(|group| {
+            group
+                .iter()
+                .map(|&index| &self.registered[index])
+                .filter(|entry| entry.is_enabled)
+                .map(|entry| (entry.id, entry.reaction.as_ref()))
+        })
+    }
+
+    // All reactions, including the disabled ones, in the order they run in
+    pub fn iter_info(&self) -> impl Iterator<Item = ReactionInfo> + '_ {
+        self.groups.iter().flatten().map(|&index| {
+            let entry = &self.registered[index];
+            ReactionInfo {
+                id: entry.id,
+                name: entry.reaction.get_name().map(String::from),
+                stage: entry.reaction.get_stage(),
+                priority: entry.reaction.get_priority(),
+                is_enabled: entry.is_enabled,
+            }
+        })
+    }
+
+    // Whether every reaction is enabled, in ascending order of ids
+    pub fn iter_enabled(&self) -> impl Iterator<Item = (ReactionId, bool)> + '_ {
+        self.registered
+            .iter()
+            .map(|entry| (entry.id, entry.is_enabled))
+    }
+
+    pub fn find(&self, name: &str) -> Option<ReactionId> {
+        self.registered
+            .iter()
+            .find(|entry| entry.reaction.get_name() == Some(name))
+            .map(|entry| entry.id)
+    }
+}
+
+impl super::Essentia {
+    // Registered reactions in the order they run in, including the disabled ones
+    pub fn list_reactions(&self) -> Vec<ReactionInfo> {
+        self.reactions.iter_info().collect()
+    }
+
+    pub fn find_reaction(&self, name: &str) -> Option<ReactionId> {
+        self.reactions.find(name)
+    }
+
+    pub fn add_reaction(
+        &mut self,
+        reaction: Box<dyn Reaction>,
+    ) -> Result<ReactionId, OrderingError> {
+        let id = Rc::make_mut(&mut self.reactions).insert(Rc::from(reaction))?;
+        self.record_reactions();
+        Ok(id)
+    }
+
+    pub fn remove_reaction(&mut self, id: ReactionId) -> bool {
+        let is_removed = Rc::make_mut(&mut self.reactions).remove(id).is_some();
+        if is_removed {
+            self.record_reactions();
+        }
+        is_removed
+    }
+
+    pub fn set_reaction_enabled(&mut self, id: ReactionId, is_enabled: bool) -> bool {
+        let is_found = Rc::make_mut(&mut self.reactions).set_enabled(id, is_enabled);
+        if is_found {
+            self.record_reactions();
+        }
+        is_found
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use super::{OrderingError, ReactionLookup};
+    use crate::{
+        engine::ReactionContext,
+        reaction::{Product, Reaction},
+    };
+
+    struct Named {
+        name: &'static str,
+        after: Vec<&'static str>,
+        before: Vec<&'static str>,
+    }
+
+    impl Reaction for Named {
+        fn react(&self, _context: &ReactionContext) -> Vec<Product> {
+            vec![]
+        }
+
+        fn get_name(&self) -> Option<&str> {
+            Some(self.name)
+        }
+
+        fn get_run_after(&self) -> Vec<&str> {
+            self.after.clone()
+        }
+
+        fn get_run_before(&self) -> Vec<&str> {
+            self.before.clone()
+        }
+    }
+
+    fn named(
+        name: &'static str,
+        after: Vec<&'static str>,
+        before: Vec<&'static str>,
+    ) -> Rc<dyn Reaction> {
+        Rc::new(Named {
+            name,
+            after,
+            before,
+        })
+    }
+
+    fn names(lookup: &ReactionLookup) -> Vec<Vec<&str>> {
+        lookup
+            .iter_groups()
+            .map(|group| {
+                group
+                    .map(|(_, reaction)| reaction.get_name().unwrap())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reactions_wait_for_each_other() {
+        let lookup = ReactionLookup::new(vec![
+            named("c", vec!["b"], vec![]),
+            named("b", vec![], vec![]),
+            named("a", vec![], vec!["b"]),
+            named("d", vec![], vec![]),
+        ])
+        .unwrap();
+        assert_eq!(names(&lookup), vec![vec!["a", "d"], vec!["b"], vec!["c"]]);
+
+        let cycle = ReactionLookup::new(vec![
+            named("a", vec!["c"], vec![]),
+            named("b", vec!["a"], vec![]),
+            named("c", vec!["b"], vec![]),
+        ]);
+        assert!(matches!(cycle, Err(OrderingError::Cycle(names)) if names.len() == 3));
+
+        // Reaction downstream of a cycle isn't a part of it
+        let cycle = ReactionLookup::new(vec![
+            named("c", vec!["a"], vec![]),
+            named("a", vec!["b"], vec![]),
+            named("b", vec!["a"], vec![]),
+        ]);
+        assert_eq!(
+            cycle.err(),
+            Some(OrderingError::Cycle(vec![
+                String::from("b"),
+                String::from("a")
+            ]))
+        );
     }
 }
@@ -0,0 +1,40 @@
+use std::collections::BTreeMap;
+
+use crate::{Essence, EssenceId, Form, FormId};
+
+/// Essences and forms an engine is configured with.
+///
+/// The registry never changes once built, so it is shared by all engines
+/// forked from the same one instead of being copied.
+pub struct Registry {
+    essence_lookup: BTreeMap<EssenceId, Essence>,
+    form_lookup: BTreeMap<FormId, Form>,
+}
+
+impl Registry {
+    pub(super) fn new(
+        essence_lookup: BTreeMap<EssenceId, Essence>,
+        form_lookup: BTreeMap<FormId, Form>,
+    ) -> Self {
+        Registry {
+            essence_lookup,
+            form_lookup,
+        }
+    }
+
+    pub fn get_essence(&self, id: EssenceId) -> Option<&Essence> {
+        self.essence_lookup.get(&id)
+    }
+
+    pub fn get_form(&self, id: FormId) -> Option<&Form> {
+        self.form_lookup.get(&id)
+    }
+
+    pub fn iter_essences(&self) -> impl Iterator<Item = &Essence> {
+        self.essence_lookup.values()
+    }
+
+    pub fn iter_forms(&self) -> impl Iterator<Item = &Form> {
+        self.form_lookup.values()
+    }
+}
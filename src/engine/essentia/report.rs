@@ -0,0 +1,62 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    physics::{Power, Time, TimeSpan},
+    reaction::{Product, ReactionId},
+};
+
+/// Where a product of a tick comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Source {
+    // Heat sources and thermal injections of the schedule
+    Injected,
+    Reaction(ReactionId),
+}
+
+/// Product as emitted by its source, before being netted with the others.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contribution {
+    pub source: Source,
+    pub product: Product,
+}
+
+/// What happened during a single simulation step and why.
+///
+/// `products` are the netted products applied to the system, while
+/// `contributions` list what every source emitted, in the order they ran in.
+/// Contributions of the same kind add up to the netted product.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TickReport {
+    pub time: Time,
+    pub delta_time: TimeSpan,
+    pub products: Vec<Product>,
+    pub contributions: Vec<Contribution>,
+}
+
+impl TickReport {
+    // Heat emitted by every source, to see which one drives the temperature
+    pub fn get_thermal_by_source(&self) -> BTreeMap<Source, Power> {
+        let mut thermal = BTreeMap::new();
+        for contribution in &self.contributions {
+            if let Product::Thermal(power) = contribution.product {
+                let total = thermal.entry(contribution.source).or_insert(Power::from(0));
+                *total = *total + power;
+            }
+        }
+        thermal
+    }
+
+    pub fn iter_from(&self, source: Source) -> impl Iterator<Item = &Product> {
+        self.contributions
+            .iter()
+            .filter(move |contribution| contribution.source == source)
+            .map(|contribution| &contribution.product)
+    }
+}
+
+impl super::Essentia {
+    // Reports of every step of the last simulation, empty unless enabled in the builder
+    pub fn get_reports(&self) -> &[TickReport] {
+        &self.reports
+    }
+}
@@ -1,42 +1,171 @@
-use std::collections::HashSet;
+use std::collections::BTreeSet;
 
 use crate::{
     abstractions::{
-        physics::{get_heat_capacity, Quantity, TimeSpan},
+        physics::{get_heat_capacity, Energy, Quantity, TimeSpan},
         reaction::Product,
         SubstanceId,
     },
-    engine::ReactionContext,
+    engine::{
+        Contribution, EnvironmentParameter, ReactionContext, RemainderKey, ScheduledAction, Source,
+        TickReport,
+    },
     physics::Solubility,
     EssenceId, FormId, Substance, SubstanceBuilder,
 };
 
 impl super::Essentia {
-    fn run_reactions(&mut self) -> ReactionContext {
-        self.reactions
-            .iter_groups()
-            .fold(ReactionContext::new(self), |context, group| {
-                let result = group
-                    .iter_reactions()
-                    .flat_map(|r| r.react(&context))
-                    .collect::<Vec<_>>();
-
-                context.apply(result)
+    fn run_reactions(&mut self) -> ReactionContext<'_> {
+        // Heat injected from the outside is known before any reaction happens
+        let injected_power = self
+            .heat_sources
+            .iter()
+            .map(|source| {
+                source.get_power(
+                    self.environment.temperature,
+                    self.heat_capacity,
+                    self.delta_time,
+                )
             })
+            .fold(self.schedule.get_injected_power(), |total, power| {
+                total + power
+            });
+        let mut context = ReactionContext::new(self);
+        if injected_power.mwatts != 0 {
+            context.contributions.push(Contribution {
+                source: Source::Injected,
+                product: Product::Thermal(injected_power),
+            });
+        }
+        context = context.apply(vec![Product::Thermal(injected_power)]);
+
+        for group in self.reactions.iter_groups() {
+            let mut result = vec![];
+            for (id, reaction) in group {
+                context.start_reaction(id);
+                let products = self.react_with_catalysis(reaction, &context);
+                context
+                    .contributions
+                    .extend(products.iter().map(|&product| Contribution {
+                        source: Source::Reaction(id),
+                        product,
+                    }));
+                result.extend(products);
+            }
+
+            context = context.apply(result);
+        }
+
+        context
     }
 
     pub fn simulate(&mut self, delta_time: TimeSpan) {
+        self.record_simulation(delta_time);
+        let end = self.environment.time + delta_time;
+        let mut is_in_equilibrium = true;
+        self.invariant_violations.clear();
+        self.reports.clear();
+
+        // Ticks are split at schedule boundaries, so that actions fire exactly on time
+        loop {
+            self.run_scheduled_actions();
+            let now = self.environment.time;
+            let step_end = self
+                .schedule
+                .get_next_boundary(now)
+                .filter(|boundary| boundary.ticks < end.ticks)
+                .unwrap_or(end);
+
+            self.simulate_step(step_end.saturating_since(now));
+            is_in_equilibrium &= self.is_in_equilibrium;
+
+            if self.environment.time.ticks >= end.ticks {
+                break;
+            }
+        }
+        self.run_scheduled_actions();
+
+        self.is_in_equilibrium = is_in_equilibrium;
+        self.finish_recording();
+    }
+
+    fn run_scheduled_actions(&mut self) {
+        for action in self.schedule.take_due(self.environment.time) {
+            self.apply_action(action);
+        }
+    }
+
+    // Performs an action immediately, thermal injections start with the next simulation
+    pub fn perform(&mut self, action: ScheduledAction) {
+        self.record_action(action);
+        self.apply_action(action);
+        self.finish_recording();
+    }
+
+    fn apply_action(&mut self, action: ScheduledAction) {
+        match action {
+            ScheduledAction::AddSubstance(essence_id, form_id, quantity) => {
+                self.produce_substance(essence_id, form_id, quantity);
+            }
+            ScheduledAction::RemoveSubstance(essence_id, form_id, quantity) => {
+                self.consume_substance(essence_id, form_id, quantity);
+            }
+            ScheduledAction::SetEnvironment(EnvironmentParameter::Temperature(temperature)) => {
+                // Heat carried towards the old temperature doesn't matter anymore
+                self.remainders.take_heat();
+                self.environment.temperature = temperature;
+            }
+            ScheduledAction::SetEnvironment(EnvironmentParameter::Pressure(pressure)) => {
+                self.environment.pressure = pressure;
+            }
+            ScheduledAction::Agitate => self.is_agitated = true,
+            ScheduledAction::SetThermostat(target) => {
+                for source in self.heat_sources.iter_mut() {
+                    if let Some(thermostat) = source.thermostat.as_mut() {
+                        thermostat.target = target;
+                        source.is_on = true;
+                    }
+                }
+            }
+            // Thermal injections are tracked by the schedule itself
+            ScheduledAction::InjectThermal(_, _) => {
+                self.schedule.add(self.environment.time, action);
+            }
+        }
+    }
+
+    fn simulate_step(&mut self, delta_time: TimeSpan) {
         self.delta_time = delta_time;
         self.heat_capacity = get_heat_capacity(self);
 
-        let mut products = self.run_reactions().pending_products;
+        let context = self.run_reactions();
+        let mut products = context.pending_products;
+        let contributions = context.contributions;
+        let carried_heat = context.carried_heat.into_inner();
+        self.remainders = context.remainders.into_inner();
 
         self.is_in_equilibrium = products.is_empty();
 
+        let invariant_check = self.check_invariants.then(|| {
+            (
+                self.take_invariant_snapshot(&contributions, &carried_heat),
+                products.clone(),
+            )
+        });
+
+        if self.keep_reports {
+            self.reports.push(TickReport {
+                time: self.environment.time,
+                delta_time,
+                products: products.clone(),
+                contributions,
+            });
+        }
+
+        let mut heat = Energy::from(0);
         products.drain(..).for_each(|p| match p {
             Product::Thermal(power) => {
-                let delta_e = power * delta_time;
-                self.environment.temperature += self.heat_capacity.get_delta_temp(delta_e);
+                heat += power * delta_time;
             }
             Product::Produce(essence_id, form_id, quantity) => {
                 self.produce_substance(essence_id, form_id, quantity);
@@ -51,8 +180,45 @@ impl super::Essentia {
                 self.precipitate_substance(essence_id, form_id, substance_id, quantity);
             }
         });
+        self.apply_heat(heat);
+
+        if let Some((snapshot, applied_products)) = invariant_check {
+            let violations = snapshot.verify(self, &applied_products, delta_time);
+            self.invariant_violations.extend(violations);
+        }
+
+        // Substances that are gone won't ever pick up their remainders
+        let substances = &self.substances;
+        self.remainders.retain(|key| match key {
+            RemainderKey::Dissolve(_, id) | RemainderKey::Precipitate(_, id) => {
+                substances.contains_key(id)
+            }
+            RemainderKey::Transition(_, _) => true,
+        });
 
         self.environment.time += self.delta_time;
+        self.is_agitated = false;
+    }
+
+    // Heat that doesn't add up to a whole mK is carried over to the next tick
+    fn apply_heat(&mut self, energy: Energy) {
+        let heat = energy + self.remainders.take_heat();
+        // Without anything to hold it, the heat is lost
+        if let Some(delta_temp) = self.heat_capacity.checked_delta_temp(heat) {
+            let capacity = i64::try_from(self.heat_capacity.joule_per_mkelvin).unwrap_or(i64::MAX);
+            self.environment.temperature += delta_temp;
+            self.remainders
+                .carry_heat(heat - Energy::from(delta_temp.mkelvin.saturating_mul(capacity)));
+        }
+    }
+
+    // Shakes the system during the next simulation step
+    pub fn agitate(&mut self) {
+        self.perform(ScheduledAction::Agitate);
+    }
+
+    pub fn is_agitated(&self) -> bool {
+        self.is_agitated
     }
 
     fn dissolve_substance(
@@ -67,7 +233,7 @@ impl super::Essentia {
         if let Some(solvent) = solvent {
             let solutes = self
                 .substances
-                .extract_if(|id, _| solute_ids.contains(id))
+                .extract_if(.., |id, _| solute_ids.contains(id))
                 .collect::<Vec<_>>();
             let mut solution_builder = SubstanceBuilder::new(self).is_solution().with_base(solvent);
 
@@ -129,7 +295,7 @@ impl super::Essentia {
         &mut self,
         essence_id: EssenceId,
         form_id: FormId,
-    ) -> HashSet<SubstanceId> {
+    ) -> BTreeSet<SubstanceId> {
         self.substances
             .values()
             .filter_map(|substance| {
@@ -143,11 +309,11 @@ impl super::Essentia {
                 None
             })
             .copied()
-            .collect::<HashSet<_>>()
+            .collect::<BTreeSet<_>>()
     }
 
     fn produce_substance(&mut self, essence_id: EssenceId, form_id: FormId, quantity: Quantity) {
-        for (_, substance) in self.substances.iter_mut() {
+        for substance in self.substances.values_mut() {
             match substance {
                 Substance::Free(_, data) => {
                     if data.essence_id == essence_id && data.form_id == form_id {
@@ -1,6 +1,32 @@
 mod essentia;
-pub use essentia::{Essentia, EssentiaBuilder};
+pub use essentia::{
+    Contribution, Essentia, EssentiaBuilder, InvariantViolation, OrderingError, ReactionInfo,
+    RewindError, Source, StateDifference, TickReport,
+};
 
 pub mod builtin_reactions;
 mod reaction_context;
 pub use reaction_context::ReactionContext;
+
+mod rng;
+pub use rng::Rng;
+
+mod remainders;
+pub use remainders::{RemainderKey, Remainders};
+
+mod apparatus;
+pub use apparatus::{HeatSource, Thermostat};
+
+mod schedule;
+pub use schedule::{EnvironmentParameter, Schedule, ScheduledAction};
+
+mod recipe;
+pub use recipe::{
+    Condition, Recipe, RecipeBuilder, RecipeReport, RecipeStep, StepOutcome, StepReport, Verdict,
+};
+
+mod discovery;
+pub use discovery::{Discovery, DiscoveryBuilder, Operation};
+
+mod network;
+pub use network::{MatterCreation, ReactionKind, ReactionNetwork, ReactionNode, Species};
@@ -0,0 +1,463 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    engine::Essentia,
+    physics::Solubility,
+    reaction::{DeclarativeReaction, Reagent},
+    EssenceId, FormId,
+};
+
+/// Essence/form node of the reaction network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Species {
+    Free(EssenceId, FormId),
+    Dissolved(EssenceId),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReactionKind {
+    // Index of the declarative reaction in the order the engine applies them
+    Declarative { index: usize, is_reverse: bool },
+    PhaseTransition(EssenceId),
+    Dissolution,
+    Precipitation,
+}
+
+/// Reaction node of the network, connecting the species it turns into others.
+///
+/// Required species (solvents and required catalysts) have to be present for
+/// the reaction to happen, but are not used up by it.
+#[derive(Debug, Clone)]
+pub struct ReactionNode {
+    pub kind: ReactionKind,
+    pub consumes: Vec<(Species, u64)>,
+    pub requires: Vec<Species>,
+    pub produces: Vec<(Species, u64)>,
+}
+
+impl ReactionNode {
+    // Mols produced per mol consumed
+    fn get_gain(&self) -> f64 {
+        let consumed = self.consumes.iter().map(|(_, c)| *c).sum::<u64>();
+        let produced = self.produces.iter().map(|(_, c)| *c).sum::<u64>();
+        produced as f64 / consumed as f64
+    }
+}
+
+/// Chain of reactions producing more matter than it consumes when run in a loop.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatterCreation {
+    pub reactions: Vec<usize>,
+    pub gain: f64,
+}
+
+/// Static, bipartite graph of species and the reactions between them.
+///
+/// It is built from declarative reactions, phase graphs and solubility roles
+/// of the registered essences. Reactions implemented by hand are opaque and
+/// so are left out.
+pub struct ReactionNetwork {
+    pub species: Vec<Species>,
+    pub reactions: Vec<ReactionNode>,
+}
+
+const GAIN_TOLERANCE: f64 = 1e-9;
+
+impl ReactionNetwork {
+    pub fn from_engine(engine: &Essentia) -> Self {
+        let mut reactions = vec![];
+
+        let declarative = engine
+            .iter_reactions()
+            .filter_map(|reaction| reaction.as_declarative());
+        for (index, reaction) in declarative.enumerate() {
+            reactions.push(Self::declarative_node(index, reaction, false));
+            if reaction.reverse_kinetics.is_some() {
+                reactions.push(Self::declarative_node(index, reaction, true));
+            }
+        }
+
+        let mut essences = engine.iter_essences().collect::<Vec<_>>();
+        essences.sort_by_key(|essence| essence.id);
+
+        for essence in &essences {
+            let mut transitions = essence
+                .phase_graph
+                .iter()
+                .flat_map(|graph| graph.iter_transitions())
+                .collect::<Vec<_>>();
+            transitions.sort_by_key(|transition| transition.threshold.mkelvin);
+
+            // Transitions go both ways, depending on whether the system is heated or cooled
+            for transition in transitions {
+                let left = Species::Free(essence.id, transition.left_form_id);
+                let right = Species::Free(essence.id, transition.right_form_id);
+                for (from, to) in [(left, right), (right, left)] {
+                    reactions.push(ReactionNode {
+                        kind: ReactionKind::PhaseTransition(essence.id),
+                        consumes: vec![(from, 1)],
+                        requires: vec![],
+                        produces: vec![(to, 1)],
+                    });
+                }
+            }
+        }
+
+        let solvents = essences
+            .iter()
+            .filter_map(|essence| match essence.solubility {
+                Some(Solubility::Solvent(form_id, _)) => Some(Species::Free(essence.id, form_id)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        for essence in &essences {
+            if let Some(Solubility::Solute(form_id, _)) = essence.solubility {
+                let solute = Species::Free(essence.id, form_id);
+                let dissolved = Species::Dissolved(essence.id);
+                for &solvent in &solvents {
+                    reactions.push(ReactionNode {
+                        kind: ReactionKind::Dissolution,
+                        consumes: vec![(solute, 1)],
+                        requires: vec![solvent],
+                        produces: vec![(dissolved, 1)],
+                    });
+                }
+                if !solvents.is_empty() {
+                    reactions.push(ReactionNode {
+                        kind: ReactionKind::Precipitation,
+                        consumes: vec![(dissolved, 1)],
+                        requires: vec![],
+                        produces: vec![(solute, 1)],
+                    });
+                }
+            }
+        }
+
+        let mut species = reactions
+            .iter()
+            .flat_map(|node| {
+                node.consumes
+                    .iter()
+                    .chain(node.produces.iter())
+                    .map(|(species, _)| *species)
+                    .chain(node.requires.iter().copied())
+            })
+            .collect::<Vec<_>>();
+        species.sort();
+        species.dedup();
+
+        ReactionNetwork { species, reactions }
+    }
+
+    fn declarative_node(
+        index: usize,
+        reaction: &DeclarativeReaction,
+        is_reverse: bool,
+    ) -> ReactionNode {
+        let to_species = |reagents: &[Reagent]| {
+            reagents
+                .iter()
+                .map(|r| (Species::Free(r.essence_id, r.form_id), r.coefficient))
+                .collect::<Vec<_>>()
+        };
+        let (consumes, produces) = if is_reverse {
+            (
+                to_species(&reaction.products),
+                to_species(&reaction.reactants),
+            )
+        } else {
+            (
+                to_species(&reaction.reactants),
+                to_species(&reaction.products),
+            )
+        };
+
+        ReactionNode {
+            kind: ReactionKind::Declarative { index, is_reverse },
+            consumes,
+            requires: reaction
+                .catalysts
+                .iter()
+                .filter(|catalyst| catalyst.is_required)
+                .map(|catalyst| Species::Free(catalyst.essence_id, catalyst.form_id))
+                .collect(),
+            produces,
+        }
+    }
+
+    // Every species that can appear when starting from the given inventory
+    pub fn get_reachable(&self, inventory: &[Species]) -> BTreeSet<Species> {
+        let mut reachable = inventory.iter().copied().collect::<BTreeSet<_>>();
+        let mut is_changed = true;
+
+        while is_changed {
+            is_changed = false;
+            for node in &self.reactions {
+                let can_happen = node
+                    .consumes
+                    .iter()
+                    .map(|(species, _)| species)
+                    .chain(node.requires.iter())
+                    .all(|species| reachable.contains(species));
+
+                if can_happen {
+                    for (species, _) in &node.produces {
+                        is_changed |= reachable.insert(*species);
+                    }
+                }
+            }
+        }
+
+        reachable
+    }
+
+    pub fn is_reachable(&self, inventory: &[Species], target: Species) -> bool {
+        self.get_reachable(inventory).contains(&target)
+    }
+
+    // Species that can be produced, but never turn into anything else
+    pub fn get_dead_ends(&self) -> Vec<Species> {
+        let used = self
+            .reactions
+            .iter()
+            .flat_map(|node| {
+                node.consumes
+                    .iter()
+                    .map(|(species, _)| *species)
+                    .chain(node.requires.iter().copied())
+            })
+            .collect::<BTreeSet<_>>();
+
+        self.species
+            .iter()
+            .filter(|species| !used.contains(species))
+            .copied()
+            .collect()
+    }
+
+    /// Finds reactions and loops of reactions creating matter from nothing.
+    ///
+    /// Every consumed species is connected to every produced one by the mol
+    /// ratio of the reaction. A loop whose ratios multiply to more than one
+    /// produces more than it started with, which is found as a negative cycle
+    /// of the logarithms by Bellman-Ford.
+    pub fn find_matter_creation(&self) -> Vec<MatterCreation> {
+        let mut found = self
+            .reactions
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.consumes.is_empty() && !node.produces.is_empty())
+            .map(|(index, _)| MatterCreation {
+                reactions: vec![index],
+                gain: f64::INFINITY,
+            })
+            .collect::<Vec<_>>();
+
+        let lookup = self
+            .species
+            .iter()
+            .enumerate()
+            .map(|(index, species)| (*species, index))
+            .collect::<BTreeMap<_, _>>();
+
+        // (from, to, reaction, gain)
+        let edges = self
+            .reactions
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| !node.consumes.is_empty())
+            .flat_map(|(index, node)| {
+                let gain = node.get_gain();
+                node.consumes.iter().flat_map(move |(from, _)| {
+                    node.produces
+                        .iter()
+                        .map(move |(to, _)| (*from, *to, index, gain))
+                })
+            })
+            .map(|(from, to, index, gain)| (lookup[&from], lookup[&to], index, gain))
+            .collect::<Vec<_>>();
+
+        let count = self.species.len();
+        let mut distance = vec![0.0; count];
+        let mut predecessor: Vec<Option<usize>> = vec![None; count];
+        for _ in 0..count {
+            for (edge_index, &(from, to, _, gain)) in edges.iter().enumerate() {
+                let candidate = distance[from] - gain.ln();
+                if candidate < distance[to] - GAIN_TOLERANCE {
+                    distance[to] = candidate;
+                    predecessor[to] = Some(edge_index);
+                }
+            }
+        }
+
+        let mut seen = BTreeSet::<Vec<usize>>::new();
+        for &(from, to, _, gain) in &edges {
+            if distance[from] - gain.ln() >= distance[to] - GAIN_TOLERANCE {
+                continue;
+            }
+
+            // Walking back far enough is guaranteed to end up inside the loop
+            let mut node = to;
+            for _ in 0..count {
+                node = predecessor[node].map_or(node, |edge| edges[edge].0);
+            }
+
+            let mut cycle = vec![];
+            let mut current = node;
+            while let Some(edge) = predecessor[current] {
+                cycle.push(edge);
+                current = edges[edge].0;
+                if current == node || cycle.len() > count {
+                    break;
+                }
+            }
+            if current != node {
+                continue;
+            }
+
+            let mut reactions = cycle.iter().map(|&edge| edges[edge].2).collect::<Vec<_>>();
+            reactions.sort();
+            reactions.dedup();
+            if seen.insert(reactions.clone()) {
+                found.push(MatterCreation {
+                    reactions,
+                    gain: cycle.iter().map(|&edge| edges[edge].3).product(),
+                });
+            }
+        }
+
+        found
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        engine::{Essentia, EssentiaBuilder},
+        physics::{Energy, PhaseTransition, Ratio, Temperature},
+        reaction::{DeclarativeReactionBuilder, Reagent},
+        Builder, EssenceBuilder, EssenceId, Form, FormId,
+    };
+
+    use super::{ReactionKind, ReactionNetwork, Species};
+
+    const LIQUID: u16 = 1;
+    const GAS: u16 = 2;
+    const SOLID: u16 = 3;
+
+    fn free(essence: u16, form: u16) -> Species {
+        Species::Free(EssenceId::from(essence), FormId::from(form))
+    }
+
+    fn reagent(essence: u16, form: u16, coefficient: u64) -> Reagent {
+        Reagent::new(essence.into(), form.into()).with_coefficient(coefficient)
+    }
+
+    // 1 - water, 2 - salt, 3 - acid, 4 - base, 5 - brine
+    fn build_engine(builder: DeclarativeReactionBuilder) -> Essentia {
+        EssentiaBuilder::new()
+            .register_form(Form::new_with_id(LIQUID.into(), "Liquid"))
+            .register_form(Form::new_with_id(GAS.into(), "Gas"))
+            .register_form(Form::new_with_id(SOLID.into(), "Solid"))
+            .register_essence(
+                EssenceBuilder::default()
+                    .with_custom_id(1.into())
+                    .with_phase_transitions(|builder| {
+                        builder.add_transition(PhaseTransition {
+                            threshold: Temperature::from(373_000),
+                            joules_per_mol: Energy::from(10),
+                            left_form_id: LIQUID.into(),
+                            right_form_id: GAS.into(),
+                            pressure_curve: None,
+                            hysteresis: None,
+                        });
+                    })
+                    .with_solubility(|builder| {
+                        builder.is_solvent().when_in_form(LIQUID.into()).build()
+                    })
+                    .build(),
+            )
+            .register_essence(
+                EssenceBuilder::default()
+                    .with_custom_id(2.into())
+                    .with_solubility(|builder| {
+                        builder.is_soluble().when_in_form(SOLID.into()).build()
+                    })
+                    .build(),
+            )
+            .register_reaction(Box::new(builder.build()))
+            .build()
+    }
+
+    fn neutralization() -> DeclarativeReactionBuilder {
+        DeclarativeReactionBuilder::default()
+            .with_reactant(reagent(3, LIQUID, 1))
+            .with_reactant(reagent(4, LIQUID, 1))
+            .with_product(reagent(2, SOLID, 1))
+            .with_product(reagent(1, LIQUID, 1))
+    }
+
+    #[test]
+    fn finds_reachable_species() {
+        let engine = build_engine(neutralization());
+        let network = ReactionNetwork::from_engine(&engine);
+
+        let inventory = [free(3, LIQUID), free(4, LIQUID)];
+        let reachable = network.get_reachable(&inventory);
+        assert!(reachable.contains(&free(1, GAS)));
+        assert!(reachable.contains(&Species::Dissolved(2.into())));
+
+        // Without the base, there is no way to get any salt
+        assert!(!network.is_reachable(&[free(3, LIQUID), free(1, LIQUID)], free(2, SOLID)));
+        assert!(network
+            .reactions
+            .iter()
+            .any(|node| node.kind == ReactionKind::Dissolution));
+    }
+
+    #[test]
+    fn finds_dead_ends() {
+        let engine = build_engine(
+            DeclarativeReactionBuilder::default()
+                .with_reactant(reagent(3, LIQUID, 1))
+                .with_product(reagent(5, LIQUID, 1)),
+        );
+        let network = ReactionNetwork::from_engine(&engine);
+
+        assert_eq!(network.get_dead_ends(), vec![free(5, LIQUID)]);
+    }
+
+    #[test]
+    fn balanced_reversible_reaction_creates_nothing() {
+        let engine = build_engine(neutralization().with_equilibrium_constant(Ratio::from(2)));
+        let network = ReactionNetwork::from_engine(&engine);
+
+        assert!(network.find_matter_creation().is_empty());
+    }
+
+    #[test]
+    fn detects_matter_creating_loop() {
+        // 1 mol of acid turns into 2 mol of base, each of which turns back into acid
+        let engine = EssentiaBuilder::new()
+            .register_reaction(Box::new(
+                DeclarativeReactionBuilder::default()
+                    .with_reactant(reagent(3, LIQUID, 1))
+                    .with_product(reagent(4, LIQUID, 2))
+                    .build(),
+            ))
+            .register_reaction(Box::new(
+                DeclarativeReactionBuilder::default()
+                    .with_reactant(reagent(4, LIQUID, 1))
+                    .with_product(reagent(3, LIQUID, 1))
+                    .build(),
+            ))
+            .build();
+        let network = ReactionNetwork::from_engine(&engine);
+
+        let found = network.find_matter_creation();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].reactions, vec![0, 1]);
+        assert!((found[0].gain - 2.0).abs() < 1e-9);
+    }
+}
@@ -1,15 +1,28 @@
-use std::collections::HashMap;
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+};
 
 use crate::abstractions::SubstanceId;
-use crate::physics::Power;
-use crate::reaction::Product;
+use crate::physics::{Energy, Power, Quantity, Ratio};
+use crate::reaction::{Product, ReactionId};
 use crate::{EssenceId, FormId};
 
-use super::Essentia;
+use super::{Contribution, Essentia, RemainderKey, Remainders, Rng, Source};
 
 pub struct ReactionContext<'a> {
     pub engine: &'a Essentia,
     pub pending_products: Vec<Product>,
+    // Products as emitted by every source, before netting
+    pub contributions: Vec<Contribution>,
+    // Written back to the engine once all reactions have run
+    pub remainders: RefCell<Remainders>,
+    // Heat every source carried over to the next tick instead of emitting it as power
+    pub carried_heat: RefCell<BTreeMap<Source, Energy>>,
+    // Stream of the reaction that is currently running
+    rng: RefCell<Rng>,
+    source: Cell<Source>,
+    rate_multiplier: Cell<Ratio>,
 }
 
 impl<'a> ReactionContext<'a> {
@@ -17,19 +30,84 @@ impl<'a> ReactionContext<'a> {
         Self {
             engine,
             pending_products: vec![],
+            contributions: vec![],
+            remainders: RefCell::new(engine.get_remainders().clone()),
+            carried_heat: RefCell::new(BTreeMap::new()),
+            rng: RefCell::new(Rng::for_reaction(
+                engine.seed,
+                engine.environment.time.ticks,
+                0,
+            )),
+            source: Cell::new(Source::Injected),
+            rate_multiplier: Cell::new(Ratio::one()),
         }
     }
 
+    // Random numbers for the running reaction, the same on every run with the same seed.
+    // The stream is borrowed only for the duration of each call
+    pub fn next_u64(&self) -> u64 {
+        self.rng.borrow_mut().next_u64()
+    }
+
+    pub fn next_below(&self, bound: u64) -> u64 {
+        self.rng.borrow_mut().next_below(bound)
+    }
+
+    pub fn next_ratio(&self) -> Ratio {
+        self.rng.borrow_mut().next_ratio()
+    }
+
+    pub fn chance(&self, probability: Ratio) -> bool {
+        self.rng.borrow_mut().chance(probability)
+    }
+
+    // Speed-up by catalysts and inhibitors, applied by declarative reactions to their own progress
+    pub fn get_rate_multiplier(&self) -> Ratio {
+        self.rate_multiplier.get()
+    }
+
+    pub(crate) fn set_rate_multiplier(&self, multiplier: Ratio) {
+        self.rate_multiplier.set(multiplier);
+    }
+
+    pub(crate) fn start_reaction(&self, reaction: ReactionId) {
+        self.source.set(Source::Reaction(reaction));
+        self.rate_multiplier.set(Ratio::one());
+        *self.rng.borrow_mut() = Rng::for_reaction(
+            self.engine.seed,
+            self.engine.environment.time.ticks,
+            u64::from(reaction),
+        );
+    }
+
+    // Splits off a part of the quantity, carrying what is lost to rounding over to the next tick
+    pub fn take_share(&self, key: RemainderKey, quantity: Quantity, ratio: Ratio) -> Quantity {
+        self.remainders
+            .borrow_mut()
+            .take_share(key, quantity, ratio)
+    }
+
+    // Power that delivers the energy over the tick, carrying what is lost to rounding
+    // over to the next tick
+    pub fn to_power(&self, energy: Energy) -> Power {
+        let delta_time = self.engine.delta_time;
+        let power = energy / delta_time;
+        let carried = energy - power * delta_time;
+        self.remainders.borrow_mut().carry_heat(carried);
+        *self
+            .carried_heat
+            .borrow_mut()
+            .entry(self.source.get())
+            .or_insert(Energy::from(0)) += carried;
+        power
+    }
+
     pub fn apply(self, products: Vec<Product>) -> Self {
         let mut thermal_product = Product::Thermal(Power::from(0));
-        let mut substance_products = HashMap::<(EssenceId, FormId), Product>::new();
-        let mut dissolution_products = HashMap::<(EssenceId, FormId, SubstanceId), Product>::new();
+        let mut substance_products = BTreeMap::<(EssenceId, FormId), Product>::new();
+        let mut dissolution_products = BTreeMap::<(EssenceId, FormId, SubstanceId), Product>::new();
 
-        for product in self
-            .pending_products
-            .into_iter()
-            .chain(products.into_iter())
-        {
+        for product in self.pending_products.into_iter().chain(products) {
             match product {
                 Product::Thermal(_) => {
                     thermal_product = thermal_product + product;
@@ -94,6 +172,12 @@ impl<'a> ReactionContext<'a> {
         ReactionContext {
             engine: self.engine,
             pending_products: products_vec,
+            contributions: self.contributions,
+            remainders: self.remainders,
+            carried_heat: self.carried_heat,
+            rng: self.rng,
+            source: self.source,
+            rate_multiplier: self.rate_multiplier,
         }
     }
 }
@@ -0,0 +1,285 @@
+use crate::{
+    engine::{Essentia, ScheduledAction},
+    physics::{Quantity, Temperature, Time, TimeSpan},
+    EssenceId, FormId,
+};
+
+/// Condition on the state of the system, used to wait in a recipe and to
+/// declare its expected outcome.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Condition {
+    TemperatureAtLeast(Temperature),
+    TemperatureAtMost(Temperature),
+    Equilibrium,
+    QuantityAtLeast(EssenceId, FormId, Quantity),
+    QuantityAtMost(EssenceId, FormId, Quantity),
+    // Counts the essence in any form, including dissolved in solutions
+    TotalAtLeast(EssenceId, Quantity),
+}
+
+impl Condition {
+    pub fn is_met(&self, engine: &Essentia) -> bool {
+        match *self {
+            Condition::TemperatureAtLeast(temperature) => {
+                engine.environment.temperature >= temperature
+            }
+            Condition::TemperatureAtMost(temperature) => {
+                engine.environment.temperature <= temperature
+            }
+            Condition::Equilibrium => engine.is_in_equilibrium,
+            Condition::QuantityAtLeast(essence_id, form_id, quantity) => {
+                engine.get_quantity_of(essence_id, form_id) >= quantity
+            }
+            Condition::QuantityAtMost(essence_id, form_id, quantity) => {
+                engine.get_quantity_of(essence_id, form_id) <= quantity
+            }
+            Condition::TotalAtLeast(essence_id, quantity) => {
+                engine
+                    .get_essence_totals()
+                    .get(&essence_id)
+                    .copied()
+                    .unwrap_or(Quantity::none())
+                    >= quantity
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecipeStep {
+    Perform(ScheduledAction),
+    Wait(TimeSpan),
+    // Simulates at least one tick, then until the condition is met
+    WaitUntil(Condition),
+    // Brings the system to the temperature with thermostats, then holds it there.
+    // Thermostats keep the new target once the step is over
+    Hold(Temperature, TimeSpan),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome {
+    Done,
+    TimedOut,
+    // Temperature can't be held without a thermostat controlled heat source
+    NoThermostat,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct StepReport {
+    pub step: RecipeStep,
+    pub started_at: Time,
+    pub finished_at: Time,
+    pub temperature: Temperature,
+    pub outcome: StepOutcome,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Success,
+    // Index of the step that never finished, the rest of the recipe was not run
+    TimedOut(usize),
+    // Index of the hold step that had no thermostat to use, the rest of the recipe was not run
+    NoThermostat(usize),
+    Unmet(Vec<Condition>),
+}
+
+#[derive(Debug, Clone)]
+pub struct RecipeReport {
+    pub steps: Vec<StepReport>,
+    pub verdict: Verdict,
+}
+
+impl RecipeReport {
+    pub fn is_success(&self) -> bool {
+        self.verdict == Verdict::Success
+    }
+}
+
+// How close to the target temperature counts as reached when holding it
+const HOLD_TOLERANCE_MK: u64 = 1000;
+
+/// Sequence of steps performed on the system, along with the expected outcome.
+pub struct Recipe {
+    pub steps: Vec<RecipeStep>,
+    pub expectations: Vec<Condition>,
+    pub tick: TimeSpan,
+    pub timeout: TimeSpan,
+}
+
+impl Recipe {
+    pub fn run(&self, engine: &mut Essentia) -> RecipeReport {
+        let mut steps = vec![];
+
+        for (index, &step) in self.steps.iter().enumerate() {
+            let started_at = engine.environment.time;
+            let outcome = self.run_step(engine, step);
+            steps.push(StepReport {
+                step,
+                started_at,
+                finished_at: engine.environment.time,
+                temperature: engine.environment.temperature,
+                outcome,
+            });
+
+            let verdict = match outcome {
+                StepOutcome::Done => continue,
+                StepOutcome::TimedOut => Verdict::TimedOut(index),
+                StepOutcome::NoThermostat => Verdict::NoThermostat(index),
+            };
+            return RecipeReport { steps, verdict };
+        }
+
+        let unmet = self
+            .expectations
+            .iter()
+            .filter(|condition| !condition.is_met(engine))
+            .copied()
+            .collect::<Vec<_>>();
+
+        RecipeReport {
+            steps,
+            verdict: if unmet.is_empty() {
+                Verdict::Success
+            } else {
+                Verdict::Unmet(unmet)
+            },
+        }
+    }
+
+    fn run_step(&self, engine: &mut Essentia, step: RecipeStep) -> StepOutcome {
+        match step {
+            RecipeStep::Perform(action) => engine.perform(action),
+            RecipeStep::Wait(time_span) => engine.simulate(time_span),
+            RecipeStep::WaitUntil(condition) => {
+                let deadline = engine.environment.time + self.timeout;
+                loop {
+                    engine.simulate(self.tick);
+                    if condition.is_met(engine) {
+                        break;
+                    }
+                    if engine.environment.time >= deadline {
+                        return StepOutcome::TimedOut;
+                    }
+                }
+            }
+            RecipeStep::Hold(temperature, time_span) => {
+                return self.hold(engine, temperature, time_span);
+            }
+        }
+
+        StepOutcome::Done
+    }
+
+    fn hold(
+        &self,
+        engine: &mut Essentia,
+        temperature: Temperature,
+        time_span: TimeSpan,
+    ) -> StepOutcome {
+        if !engine
+            .heat_sources
+            .iter()
+            .any(|source| source.thermostat.is_some())
+        {
+            return StepOutcome::NoThermostat;
+        }
+        engine.perform(ScheduledAction::SetThermostat(temperature));
+
+        let deadline = engine.environment.time + self.timeout;
+        while engine
+            .environment
+            .temperature
+            .mkelvin
+            .abs_diff(temperature.mkelvin)
+            > HOLD_TOLERANCE_MK
+        {
+            if engine.environment.time >= deadline {
+                return StepOutcome::TimedOut;
+            }
+            engine.simulate(self.tick);
+        }
+
+        engine.simulate(time_span);
+        StepOutcome::Done
+    }
+}
+
+pub struct RecipeBuilder {
+    steps: Vec<RecipeStep>,
+    expectations: Vec<Condition>,
+    tick: TimeSpan,
+    timeout: TimeSpan,
+}
+
+impl Default for RecipeBuilder {
+    fn default() -> Self {
+        Self {
+            steps: vec![],
+            expectations: vec![],
+            tick: TimeSpan::default(),
+            timeout: TimeSpan::from(10_000),
+        }
+    }
+}
+
+impl RecipeBuilder {
+    pub fn with_step(mut self, step: RecipeStep) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    pub fn with_action(self, action: ScheduledAction) -> Self {
+        self.with_step(RecipeStep::Perform(action))
+    }
+
+    pub fn with_wait(self, time_span: TimeSpan) -> Self {
+        self.with_step(RecipeStep::Wait(time_span))
+    }
+
+    pub fn with_wait_until(self, condition: Condition) -> Self {
+        self.with_step(RecipeStep::WaitUntil(condition))
+    }
+
+    pub fn with_hold(self, temperature: Temperature, time_span: TimeSpan) -> Self {
+        self.with_step(RecipeStep::Hold(temperature, time_span))
+    }
+
+    pub fn with_expectation(mut self, condition: Condition) -> Self {
+        self.expectations.push(condition);
+        self
+    }
+
+    pub fn with_expected_product(
+        self,
+        essence_id: EssenceId,
+        form_id: FormId,
+        quantity: Quantity,
+    ) -> Self {
+        self.with_expectation(Condition::QuantityAtLeast(essence_id, form_id, quantity))
+    }
+
+    // Time simulated at once while waiting on a condition
+    pub fn with_tick(mut self, tick: TimeSpan) -> Self {
+        self.tick = tick;
+        self
+    }
+
+    // Longest time to wait on a single condition
+    pub fn with_timeout(mut self, timeout: TimeSpan) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn build(self) -> Recipe {
+        if self.tick.ticks == 0 {
+            panic!("Recipe tick has to be at least one tick long!");
+        }
+
+        Recipe {
+            steps: self.steps,
+            expectations: self.expectations,
+            tick: self.tick,
+            timeout: self.timeout,
+        }
+    }
+}
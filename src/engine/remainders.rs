@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    abstractions::SubstanceId,
+    physics::{Energy, Quantity, Ratio},
+    EssenceId, FormId,
+};
+
+const MILLIONTHS: u64 = 1_000_000;
+
+/// Identifies a split of some quantity that is repeated every tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RemainderKey {
+    // Solute essence and the solvent it dissolves in
+    Dissolve(EssenceId, SubstanceId),
+    // Solute essence and the solution it falls out of
+    Precipitate(EssenceId, SubstanceId),
+    // Essence and the form it transitions out of
+    Transition(EssenceId, FormId),
+}
+
+/// Parts of a mmol lost to rounding when quantities are split.
+///
+/// They are carried over to the next tick, so that small quantities split
+/// every tick still add up instead of being rounded away. The same goes for
+/// heat that doesn't add up to a whole mK yet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Remainders {
+    // In millionths of a mmol
+    carried: BTreeMap<RemainderKey, u64>,
+    heat: Energy,
+}
+
+impl Remainders {
+    pub fn take_share(&mut self, key: RemainderKey, quantity: Quantity, ratio: Ratio) -> Quantity {
+        let (share, remainder) = ratio.apply_with_remainder(quantity);
+        let carried = self.carried.remove(&key).unwrap_or(0) + remainder;
+        let (whole, carried_over) = (carried / MILLIONTHS, carried % MILLIONTHS);
+
+        if carried_over != 0 {
+            self.carried.insert(key, carried_over);
+        }
+        share + Quantity::from(whole)
+    }
+
+    pub fn get(&self, key: RemainderKey) -> u64 {
+        self.carried.get(&key).copied().unwrap_or(0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&RemainderKey, &u64)> {
+        self.carried.iter()
+    }
+
+    pub fn retain(&mut self, mut f: impl FnMut(&RemainderKey) -> bool) {
+        self.carried.retain(|key, _| f(key));
+    }
+
+    pub fn get_heat(&self) -> Energy {
+        self.heat
+    }
+
+    pub fn carry_heat(&mut self, energy: Energy) {
+        self.heat += energy;
+    }
+
+    pub fn take_heat(&mut self) -> Energy {
+        std::mem::take(&mut self.heat)
+    }
+
+    pub fn clear(&mut self) {
+        self.carried.clear();
+        self.heat = Energy::default();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RemainderKey, Remainders};
+    use crate::physics::{Quantity, Ratio};
+
+    #[test]
+    fn remainders_add_up_over_ticks() {
+        let mut remainders = Remainders::default();
+        let key = RemainderKey::Transition(1.into(), 1.into());
+        let third = Ratio::new(1u64, 3u64);
+
+        // A third of a mmol is rounded away on its own
+        let shares = (0..3)
+            .map(|_| remainders.take_share(key, Quantity::from(1), third))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            shares,
+            vec![Quantity::none(), Quantity::none(), Quantity::none()]
+        );
+        assert_eq!(remainders.get(key), 999_999);
+        assert_eq!(
+            remainders.take_share(key, Quantity::from(1), third),
+            Quantity::from(1)
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use crate::physics::Ratio;
+
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+// Finalizer of SplitMix64, scrambles the bits of the value
+fn mix(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    value ^ (value >> 31)
+}
+
+/// Deterministic stream of pseudo-random numbers, generated with SplitMix64.
+///
+/// Reactions get a stream of their own every tick, derived from the engine's
+/// seed, the time and the reaction, so the results are the same on every run
+/// no matter how many numbers other reactions draw.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn for_reaction(seed: u64, tick: u64, reaction: u64) -> Self {
+        Rng::new(mix(mix(seed ^ mix(tick)) ^ reaction))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(GOLDEN_GAMMA);
+        mix(self.state)
+    }
+
+    // Uniformly distributed in 0..bound
+    pub fn next_below(&mut self, bound: u64) -> u64 {
+        ((self.next_u64() as u128 * bound as u128) >> 64) as u64
+    }
+
+    // Uniformly distributed between zero and one, excluding one
+    pub fn next_ratio(&mut self) -> Ratio {
+        Ratio {
+            millionths: self.next_below(Ratio::one().millionths),
+        }
+    }
+
+    pub fn chance(&mut self, probability: Ratio) -> bool {
+        self.next_ratio() < probability
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Rng;
+    use crate::physics::Ratio;
+
+    #[test]
+    fn rng_is_splitmix() {
+        let mut rng = Rng::new(0);
+        assert_eq!(rng.next_u64(), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(rng.next_u64(), 0x6e78_9e6a_a1b9_65f4);
+
+        assert!(!rng.chance(Ratio::zero()));
+        assert!(rng.chance(Ratio::one()));
+        assert!(rng.next_below(10) < 10);
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{
+    physics::{Power, Pressure, Quantity, Temperature, Time, TimeSpan},
+    EssenceId, FormId,
+};
+
+/// Environment parameter that can be changed by a scheduled action.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EnvironmentParameter {
+    Temperature(Temperature),
+    Pressure(Pressure),
+}
+
+/// Intervention performed on the system at a given point in time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScheduledAction {
+    AddSubstance(EssenceId, FormId, Quantity),
+    RemoveSubstance(EssenceId, FormId, Quantity),
+    // Heats up (or chills with negative power) the system for given time
+    InjectThermal(Power, TimeSpan),
+    SetEnvironment(EnvironmentParameter),
+    // Changes the target of every thermostat controlled heat source
+    SetThermostat(Temperature),
+    // Triggers nucleation of superheated and supercooled substances
+    Agitate,
+}
+
+/// Actions to be performed on the system, keyed by simulation time.
+///
+/// When simulating, the engine splits ticks at action boundaries so that
+/// every action fires at exactly the time it was scheduled for.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Schedule {
+    pending: Vec<(Time, ScheduledAction)>,
+    injections: Vec<(Time, Power)>,
+}
+
+impl Schedule {
+    // Actions scheduled for the same time fire in the order they were added
+    pub fn add(&mut self, at: Time, action: ScheduledAction) {
+        let index = self
+            .pending
+            .iter()
+            .position(|(time, _)| time.ticks > at.ticks)
+            .unwrap_or(self.pending.len());
+        self.pending.insert(index, (at, action));
+    }
+
+    pub fn iter_pending(&self) -> impl Iterator<Item = &(Time, ScheduledAction)> {
+        self.pending.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty() && self.injections.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.injections.clear();
+    }
+
+    // Total power of the thermal injections that are currently active
+    pub fn get_injected_power(&self) -> Power {
+        self.injections
+            .iter()
+            .fold(Power::from(0), |total, (_, power)| total + *power)
+    }
+
+    // Earliest point after `now` at which the schedule changes something
+    pub(crate) fn get_next_boundary(&self, now: Time) -> Option<Time> {
+        self.pending
+            .iter()
+            .map(|(time, _)| *time)
+            .chain(self.injections.iter().map(|(until, _)| *until))
+            .filter(|time| time.ticks > now.ticks)
+            .min_by_key(|time| time.ticks)
+    }
+
+    // Removes actions that are due. Thermal injections are kept track of
+    // until they expire, all the other actions are returned to be performed.
+    pub(crate) fn take_due(&mut self, now: Time) -> Vec<ScheduledAction> {
+        self.injections.retain(|(until, _)| until.ticks > now.ticks);
+
+        let due_count = self
+            .pending
+            .iter()
+            .take_while(|(time, _)| time.ticks <= now.ticks)
+            .count();
+
+        let mut due = vec![];
+        for (time, action) in self.pending.drain(..due_count) {
+            match action {
+                ScheduledAction::InjectThermal(power, duration) => {
+                    let until = time + duration;
+                    if until.ticks > now.ticks {
+                        self.injections.push((until, power));
+                    }
+                }
+                _ => due.push(action),
+            }
+        }
+
+        due
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Schedule, ScheduledAction};
+    use crate::physics::{Power, Quantity, Time, TimeSpan};
+
+    #[test]
+    fn splits_at_action_and_injection_boundaries() {
+        let mut schedule = Schedule::default();
+        schedule.add(
+            Time::from(10),
+            ScheduledAction::InjectThermal(Power::from(500), TimeSpan::from(20)),
+        );
+        schedule.add(
+            Time::from(5),
+            ScheduledAction::AddSubstance(0.into(), 0.into(), Quantity::default()),
+        );
+
+        assert_eq!(
+            schedule.get_next_boundary(Time::from(0)),
+            Some(Time::from(5))
+        );
+        assert_eq!(schedule.take_due(Time::from(5)).len(), 1);
+        assert_eq!(
+            schedule.get_next_boundary(Time::from(5)),
+            Some(Time::from(10))
+        );
+
+        assert!(schedule.take_due(Time::from(10)).is_empty());
+        assert_eq!(schedule.get_injected_power(), Power::from(500));
+        assert_eq!(
+            schedule.get_next_boundary(Time::from(10)),
+            Some(Time::from(30))
+        );
+
+        schedule.take_due(Time::from(30));
+        assert_eq!(schedule.get_injected_power(), Power::from(0));
+        assert!(schedule.is_empty());
+    }
+}
@@ -1,8 +1,3 @@
-#![feature(fn_traits)]
-#![feature(unboxed_closures)]
-#![feature(hash_extract_if)]
-#![feature(option_take_if)]
-
 mod abstractions;
 pub use abstractions::{
     physics, reaction, Environment, Essence, EssenceBuilder, EssenceId, Form, FormId, Substance,
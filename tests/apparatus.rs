@@ -0,0 +1,114 @@
+use data::essence::Essences;
+use data::form::Forms;
+use essentia_rs::engine::{
+    Essentia, EssentiaBuilder, HeatSource, RecipeBuilder, StepOutcome, Thermostat, Verdict,
+};
+use essentia_rs::physics::{Power, Quantity, Temperature, TimeSpan};
+use essentia_rs::SubstanceBuilder;
+
+pub mod data;
+
+fn setup(heat_source: HeatSource) -> Essentia {
+    // Create engine without built-in reactions
+    let mut builder = EssentiaBuilder::new();
+
+    builder = data::essence::create_essences()
+        .into_iter()
+        .fold(builder, |it, e| it.register_essence(e));
+
+    builder = data::form::create_forms()
+        .into_iter()
+        .fold(builder, |it, e| it.register_form(e));
+
+    let mut engine = builder.with_heat_source(heat_source).build();
+    engine.add_substance(
+        SubstanceBuilder::new(&engine)
+            .is_normal()
+            .with_essence(Essences::Heatstone.into())
+            .with_form(Forms::Salt.into())
+            .with_quantity(Quantity::from(1000))
+            .build(),
+    );
+    engine
+}
+
+#[test]
+fn burner_heats_and_chiller_cools() {
+    let mut burner_engine = setup(HeatSource::new(Power::from(10_000_000)));
+    let mut chiller_engine = setup(HeatSource::new(Power::from(-10_000_000)));
+    let start = burner_engine.environment.temperature;
+
+    burner_engine.simulate(TimeSpan::from(10));
+    chiller_engine.simulate(TimeSpan::from(10));
+
+    assert!(burner_engine.environment.temperature > start);
+    assert!(chiller_engine.environment.temperature < start);
+}
+
+#[test]
+fn thermostat_holds_target_temperature() {
+    let target = Temperature::from_kelvin(350.0);
+    let thermostat =
+        Thermostat::new(Temperature::default(), Power::from(10_000_000)).with_cooling();
+    let mut engine = setup(HeatSource::with_thermostat(thermostat));
+
+    let report = RecipeBuilder::default()
+        .with_hold(target, TimeSpan::from(100))
+        .build()
+        .run(&mut engine);
+
+    assert!(report.is_success());
+    let step = report.steps[0];
+    assert_eq!(step.outcome, StepOutcome::Done);
+    assert!(step.finished_at.ticks >= 100);
+    assert!(
+        engine
+            .environment
+            .temperature
+            .mkelvin
+            .abs_diff(target.mkelvin)
+            <= 1000
+    );
+}
+
+#[test]
+fn thermostat_keeps_target_after_hold() {
+    let target = Temperature::from_kelvin(350.0);
+    let thermostat =
+        Thermostat::new(Temperature::default(), Power::from(10_000_000)).with_cooling();
+    let mut engine = setup(HeatSource::with_thermostat(thermostat));
+
+    let report = RecipeBuilder::default()
+        .with_hold(target, TimeSpan::from(10))
+        .with_wait(TimeSpan::from(100))
+        .build()
+        .run(&mut engine);
+
+    assert!(report.is_success());
+    assert_eq!(engine.heat_sources[0].thermostat.unwrap().target, target);
+    assert!(
+        engine
+            .environment
+            .temperature
+            .mkelvin
+            .abs_diff(target.mkelvin)
+            <= 1000
+    );
+}
+
+#[test]
+fn hold_without_thermostat_is_reported() {
+    let mut engine = setup(HeatSource::new(Power::from(10_000_000)));
+
+    let report = RecipeBuilder::default()
+        .with_hold(Temperature::from_kelvin(350.0), TimeSpan::from(100))
+        .with_wait(TimeSpan::from(100))
+        .build()
+        .run(&mut engine);
+
+    assert_eq!(report.verdict, Verdict::NoThermostat(0));
+    assert_eq!(report.steps.len(), 1);
+    let step = report.steps[0];
+    assert_eq!(step.outcome, StepOutcome::NoThermostat);
+    assert_eq!(step.finished_at, step.started_at);
+}
@@ -1,7 +1,8 @@
 use data::form::Forms;
 use data::reactions::CryodustChill;
 use data::{essence::Essences, reactions::PyroflaxHeat};
-use essentia_rs::physics::Power;
+use essentia_rs::engine::{HeatSource, RemainderKey, ScheduledAction};
+use essentia_rs::physics::{Power, Pressure, Ratio};
 use essentia_rs::{
     engine::{Essentia, EssentiaBuilder},
     physics::{Quantity, Rate, Temperature, TimeSpan},
@@ -138,6 +139,30 @@ fn test_water_evaporation_transitions() {
     assert_eq!(engine.get_form(Forms::Gas.into()).into_iter().count(), 1);
 }
 
+fn get_boiling_temperature(pressure: Pressure) -> Temperature {
+    let mut engine = setup();
+    engine.environment.pressure = pressure;
+    add_water(&mut engine, Quantity::from(10_000));
+    add_pyroflux(&mut engine);
+
+    let mut trial: u32 = 0;
+    while get_of_form(&engine, Forms::Gas).count() == 0 {
+        assert_trial_limit(&mut trial);
+        engine.simulate(TimeSpan::default())
+    }
+
+    engine.environment.temperature
+}
+
+#[test]
+fn test_water_boils_lower_in_vacuum_and_higher_when_sealed() {
+    let vacuum_boiling = get_boiling_temperature(Pressure::from(20_000));
+    let sealed_boiling = get_boiling_temperature(Pressure::from(200_000));
+
+    assert!(vacuum_boiling < WATER_BOIL_TEMP);
+    assert!(sealed_boiling > WATER_BOIL_TEMP);
+}
+
 #[test]
 fn test_water_crystalization_transitions() {
     let mut engine = setup();
@@ -175,6 +200,66 @@ fn test_water_crystalization_transitions() {
     );
 }
 
+#[test]
+fn ice_melts_and_boils_within_a_single_tick() {
+    let mut engine = setup();
+    engine.environment.temperature = Temperature::from(263_000);
+    engine.add_substance(
+        SubstanceBuilder::new(&engine)
+            .is_normal()
+            .with_essence(Essences::Aqua.into())
+            .with_quantity(Quantity::from(10))
+            .with_form(Forms::Crystalline.into())
+            .build(),
+    );
+
+    // 40 J/mK to reach melting, 80 J to melt, 40 J/mK to reach boiling, 120 J to boil
+    // and 40 J/mK for the last kelvin
+    let energy = 10_000 * 40 + 80 + 100_000 * 40 + 120 + 1_000 * 40;
+    engine
+        .heat_sources
+        .push(HeatSource::new(Power::from(energy)));
+    engine.simulate(TimeSpan::from(1));
+
+    assert_eq!(get_of_form(&engine, Forms::Crystalline).count(), 0);
+    assert_eq!(get_of_form(&engine, Forms::Liquid).count(), 0);
+    assert_eq!(
+        engine.get_quantity_of(Essences::Aqua.into(), Forms::Gas.into()),
+        Quantity::from(10)
+    );
+    assert_eq!(engine.environment.temperature, Temperature::from(374_000));
+}
+
+#[test]
+fn partial_melt_stays_at_the_threshold() {
+    let mut engine = setup();
+    engine.environment.temperature = Temperature::from(272_999);
+    engine.add_substance(
+        SubstanceBuilder::new(&engine)
+            .is_normal()
+            .with_essence(Essences::Aqua.into())
+            .with_quantity(Quantity::from(10))
+            .with_form(Forms::Crystalline.into())
+            .build(),
+    );
+    engine.heat_sources.push(HeatSource::new(Power::from(1)));
+
+    // 100 J over a tick longer than the 40 J/mK heat capacity: 40 J to reach melting
+    // and 60 J to melt 7.5 mmol, which leaves nothing to heat the water with
+    engine.simulate(TimeSpan::from(100));
+    assert_eq!(engine.environment.temperature, Temperature::from(273_000));
+    assert_eq!(
+        engine.get_quantity_of(Essences::Aqua.into(), Forms::Crystalline.into()),
+        Quantity::from(3)
+    );
+
+    // Leftover ice melts next, the rest of the heat is carried into the water
+    engine.simulate(TimeSpan::from(100));
+    assert_eq!(get_of_form(&engine, Forms::Crystalline).count(), 0);
+    assert_eq!(engine.environment.temperature, Temperature::from(273_001));
+    assert_eq!(engine.get_remainders().get_heat().joules, 36);
+}
+
 #[test]
 fn test_solution_in_water() {
     let mut engine = setup();
@@ -191,12 +276,12 @@ fn test_solution_in_water() {
 
     let total_vitae = substances
         .iter()
-        .filter_map(|substance| {
+        .map(|substance| {
             match &substance {
                 Substance::Free(_, data) => {
                     if data.essence_id == Essences::Vitae.into() {
                         assert!(data.quantity < starting_vitae, "Some vitae is supposed to have dissolved!");
-                        return Some(data.quantity);
+                        return data.quantity;
                     }
                     panic!("Free non-vitae? this is not expected. All of the water should have become a solution!")
                 },
@@ -205,7 +290,7 @@ fn test_solution_in_water() {
                     assert_eq!(1, solutes.len(), "Only one solute should be present!");
                     let &vitae_solute_qty = solutes.get(&Essences::Vitae.into()).unwrap();
                     assert!(vitae_solute_qty < starting_vitae);
-                    Some(vitae_solute_qty)
+                    vitae_solute_qty
                 }
             }
         })
@@ -219,6 +304,31 @@ fn test_solution_in_water() {
     }
 }
 
+#[test]
+fn remainders_of_removed_substances_are_dropped() {
+    let mut engine = setup();
+    let water = add_water(&mut engine, Quantity::from(1_000));
+    add_vitae(&mut engine, Quantity::from(333));
+    add_saline(&mut engine, Quantity::from(333));
+    engine.simulate(TimeSpan::default());
+
+    let is_dissolving = |engine: &Essentia| {
+        engine
+            .get_remainders()
+            .iter()
+            .any(|(key, _)| matches!(key, RemainderKey::Dissolve(_, id) if *id == water))
+    };
+    assert!(is_dissolving(&engine));
+
+    engine.perform(ScheduledAction::RemoveSubstance(
+        Essences::Aqua.into(),
+        Forms::Liquid.into(),
+        Quantity::from(1_000),
+    ));
+    engine.simulate(TimeSpan::default());
+    assert!(!is_dissolving(&engine));
+}
+
 #[test]
 fn test_dissolution_equilibrium() {
     let mut engine = setup();
@@ -289,7 +399,7 @@ fn test_dissolution_equilibrium() {
                 .unwrap();
             assert_eq!(
                 solubility.get_saturation_percent(&engine, substance),
-                1.0,
+                Ratio::one(),
                 "Solution should be saturated"
             );
             solutes.iter().for_each(|(&solute_essence, &solute_qty)| {
@@ -1,5 +1,5 @@
 use essentia_rs::{
-    physics::{Energy, PerMol, PhaseTransition, SpecificHeatCapacity, Temperature},
+    physics::{Energy, PerMol, PhaseTransition, PressureCurve, SpecificHeatCapacity, Temperature},
     Builder, Essence, EssenceBuilder, EssenceId,
 };
 
@@ -34,6 +34,8 @@ pub fn create_essences() -> Vec<Essence> {
                     joules_per_mol: Energy::from(12),
                     left_form_id: Forms::Liquid.into(),
                     right_form_id: Forms::Gas.into(),
+                    pressure_curve: Some(PressureCurve::ClausiusClapeyron(Energy::from(40_650))),
+                    hysteresis: None,
                 });
 
                 builder.add_transition(PhaseTransition {
@@ -41,6 +43,8 @@ pub fn create_essences() -> Vec<Essence> {
                     joules_per_mol: Energy::from(8),
                     left_form_id: Forms::Crystalline.into(),
                     right_form_id: Forms::Liquid.into(),
+                    pressure_curve: None,
+                    hysteresis: None,
                 });
             })
             .with_solubility(|builder| {
@@ -36,8 +36,8 @@ impl Reaction for PyroflaxHeat {
     }
 }
 
-impl PyroflaxHeat {
-    pub fn default() -> Self {
+impl Default for PyroflaxHeat {
+    fn default() -> Self {
         PyroflaxHeat {
             power_per_mmol: Power::from(1),
         }
@@ -47,7 +47,7 @@ impl PyroflaxHeat {
 impl From<u16> for PyroflaxHeat {
     fn from(value: u16) -> Self {
         PyroflaxHeat {
-            power_per_mmol: Power::from(value as i32),
+            power_per_mmol: Power::from(value as i64),
         }
     }
 }
@@ -0,0 +1,150 @@
+use std::rc::Rc;
+
+use data::essence::Essences;
+use data::form::Forms;
+use data::reactions::PyroflaxHeat;
+use essentia_rs::engine::{Essentia, EssentiaBuilder, ScheduledAction, StateDifference};
+use essentia_rs::physics::{Quantity, Temperature, TimeSpan};
+
+pub mod data;
+
+fn setup() -> Essentia {
+    // Create engine WITH built-in reactions
+    let mut builder = EssentiaBuilder::default();
+
+    builder = data::essence::create_essences()
+        .into_iter()
+        .fold(builder, |it, e| it.register_essence(e));
+
+    builder = data::form::create_forms()
+        .into_iter()
+        .fold(builder, |it, e| it.register_form(e));
+
+    builder
+        .register_reaction(Box::new(PyroflaxHeat::from(42)))
+        .build()
+}
+
+fn brew() -> Essentia {
+    let mut engine = setup();
+    for (essence, form, quantity) in [
+        (Essences::Aqua, Forms::Liquid, 5_000),
+        (Essences::Vitae, Forms::Crystalline, 700),
+        (Essences::Saline, Forms::Crystalline, 900),
+        (Essences::Pyroflux, Forms::Salt, 10_000),
+    ] {
+        engine.perform(ScheduledAction::AddSubstance(
+            essence.into(),
+            form.into(),
+            Quantity::from(quantity),
+        ));
+    }
+
+    for _ in 0..50 {
+        engine.simulate(TimeSpan::from(1));
+    }
+    engine
+}
+
+#[test]
+fn identical_brews_end_up_identical() {
+    let first = brew();
+    // Substances of other engines in the process don't affect this one
+    let _ = brew();
+    let second = brew();
+
+    assert_eq!(
+        format!("{:?}", first.iter_all().collect::<Vec<_>>()),
+        format!("{:?}", second.iter_all().collect::<Vec<_>>())
+    );
+    assert_eq!(
+        first.environment.temperature,
+        second.environment.temperature
+    );
+}
+
+#[test]
+fn state_hash_detects_desync() {
+    let first = brew();
+    let mut second = brew();
+    assert_eq!(first.state_hash(), second.state_hash());
+    assert!(first.diff_state(&second).is_empty());
+
+    second.environment.temperature = Temperature::from(0);
+
+    assert_ne!(first.state_hash(), second.state_hash());
+    assert_eq!(
+        first.diff_state(&second),
+        vec![StateDifference::Temperature(
+            first.environment.temperature,
+            Temperature::from(0)
+        )]
+    );
+
+    // Reactions are a part of the state too
+    let mut third = brew();
+    let heat = third
+        .list_reactions()
+        .into_iter()
+        .find(|info| info.name.is_none())
+        .unwrap()
+        .id;
+    third.set_reaction_enabled(heat, false);
+
+    assert_ne!(first.state_hash(), third.state_hash());
+    assert_eq!(
+        first.diff_state(&third),
+        vec![StateDifference::Reaction(heat, Some(true), Some(false))]
+    );
+}
+
+#[test]
+fn forks_share_registry_but_not_state() {
+    let mut original = brew();
+    let mut fork = original.fork();
+    assert!(Rc::ptr_eq(original.get_registry(), fork.get_registry()));
+
+    let hash_before = original.state_hash();
+    fork.perform(ScheduledAction::RemoveSubstance(
+        Essences::Pyroflux.into(),
+        Forms::Salt.into(),
+        Quantity::from(10_000),
+    ));
+    fork.simulate(TimeSpan::from(10));
+    assert_eq!(original.state_hash(), hash_before);
+
+    // Forks carry on exactly like the original would
+    let mut twin = original.fork();
+    original.simulate(TimeSpan::from(10));
+    twin.simulate(TimeSpan::from(10));
+    assert_eq!(original.state_hash(), twin.state_hash());
+}
+
+#[test]
+fn substance_ids_are_never_reused() {
+    let mut engine = setup();
+    let (essence, form) = (Essences::Aqua.into(), Forms::Liquid.into());
+
+    // More substances than fit in 16 bits come and go
+    for _ in 0..70_000 {
+        engine.perform(ScheduledAction::AddSubstance(
+            essence,
+            form,
+            Quantity::from(1),
+        ));
+        engine.perform(ScheduledAction::RemoveSubstance(
+            essence,
+            form,
+            Quantity::from(1),
+        ));
+    }
+    engine.perform(ScheduledAction::AddSubstance(
+        essence,
+        form,
+        Quantity::from(1),
+    ));
+
+    let id = engine.iter_all().next().unwrap().get_substance();
+    assert_eq!(engine.iter_all().count(), 1);
+    assert_eq!(u64::from(id), 70_000);
+}
@@ -0,0 +1,72 @@
+use data::essence::Essences;
+use data::form::Forms;
+use essentia_rs::engine::{Condition, DiscoveryBuilder, Essentia, EssentiaBuilder, Operation};
+use essentia_rs::physics::{Power, Quantity, Temperature, TimeSpan};
+use essentia_rs::SubstanceBuilder;
+
+pub mod data;
+
+fn setup() -> Essentia {
+    // Create engine WITH built-in reactions
+    let mut builder = EssentiaBuilder::default();
+
+    builder = data::essence::create_essences()
+        .into_iter()
+        .fold(builder, |it, e| it.register_essence(e));
+
+    builder = data::form::create_forms()
+        .into_iter()
+        .fold(builder, |it, e| it.register_form(e));
+
+    let mut engine = builder.build();
+    engine.add_substance(
+        SubstanceBuilder::new(&engine)
+            .is_normal()
+            .with_essence(Essences::Heatstone.into())
+            .with_form(Forms::Salt.into())
+            .with_quantity(Quantity::from(100))
+            .build(),
+    );
+    engine
+}
+
+fn warm_saline() -> DiscoveryBuilder {
+    DiscoveryBuilder::default()
+        .with_operation(Operation::Wait(TimeSpan::from(5)))
+        .with_operation(Operation::Add(
+            Essences::Saline.into(),
+            Forms::Crystalline.into(),
+            Quantity::from(1000),
+        ))
+        .with_operation(Operation::Heat(
+            Power::from(5_000_000_000),
+            TimeSpan::from(10),
+        ))
+        .with_target(Condition::QuantityAtLeast(
+            Essences::Saline.into(),
+            Forms::Crystalline.into(),
+            Quantity::from(2000),
+        ))
+        .with_target(Condition::TemperatureAtLeast(Temperature::from_kelvin(
+            300.0,
+        )))
+}
+
+#[test]
+fn finds_procedure_reaching_target() {
+    let discovery = warm_saline().build();
+
+    let recipe = discovery
+        .search(&setup())
+        .expect("Expected a procedure to be found!");
+
+    let mut engine = setup();
+    assert!(recipe.run(&mut engine).is_success());
+}
+
+#[test]
+fn gives_up_past_max_depth() {
+    let discovery = warm_saline().with_max_depth(2).build();
+
+    assert!(discovery.search(&setup()).is_none());
+}
@@ -0,0 +1,118 @@
+use data::essence::Essences;
+use data::form::Forms;
+use data::reactions::PyroflaxHeat;
+use essentia_rs::engine::{Essentia, EssentiaBuilder, HeatSource, RewindError, ScheduledAction};
+use essentia_rs::physics::{Power, Quantity, Time, TimeSpan};
+
+pub mod data;
+
+fn setup(capacity: usize) -> Essentia {
+    // Create engine WITH built-in reactions
+    let mut builder = EssentiaBuilder::default();
+
+    builder = data::essence::create_essences()
+        .into_iter()
+        .fold(builder, |it, e| it.register_essence(e));
+
+    builder = data::form::create_forms()
+        .into_iter()
+        .fold(builder, |it, e| it.register_form(e));
+
+    let mut engine = builder
+        .register_reaction(Box::new(PyroflaxHeat::from(42)))
+        .with_history(TimeSpan::from(10), capacity)
+        .build();
+
+    engine.perform(ScheduledAction::AddSubstance(
+        Essences::Aqua.into(),
+        Forms::Liquid.into(),
+        Quantity::from(5_000),
+    ));
+    engine.perform(ScheduledAction::AddSubstance(
+        Essences::Pyroflux.into(),
+        Forms::Salt.into(),
+        Quantity::from(10_000),
+    ));
+    engine
+}
+
+// Simulates tick by tick, returning the state hash after every tick
+fn brew(engine: &mut Essentia, ticks: u64) -> Vec<u64> {
+    let mut hashes = vec![engine.state_hash()];
+    for tick in 0..ticks {
+        if tick == 15 {
+            engine.perform(ScheduledAction::AddSubstance(
+                Essences::Vitae.into(),
+                Forms::Crystalline.into(),
+                Quantity::from(700),
+            ));
+        }
+        engine.simulate(TimeSpan::from(1));
+        hashes.push(engine.state_hash());
+    }
+    hashes
+}
+
+#[test]
+fn rewinding_restores_the_exact_state() {
+    let mut engine = setup(100);
+    let hashes = brew(&mut engine, 50);
+
+    engine.rewind_to(Time::from(23)).unwrap();
+    assert_eq!(engine.environment.time, Time::from(23));
+    assert_eq!(engine.state_hash(), hashes[23]);
+
+    // The rewound future is kept for scrubbing forward
+    engine.rewind_to(Time::from(42)).unwrap();
+    assert_eq!(engine.state_hash(), hashes[42]);
+
+    engine.rewind_to(Time::from(16)).unwrap();
+    assert_eq!(engine.state_hash(), hashes[16]);
+    assert_eq!(engine.rewind_to(Time::from(51)), Err(RewindError::InFuture));
+}
+
+#[test]
+fn simulating_after_rewind_replaces_the_future() {
+    let mut engine = setup(100);
+    brew(&mut engine, 50);
+
+    engine.rewind_to(Time::from(10)).unwrap();
+    engine.simulate(TimeSpan::from(5));
+
+    assert_eq!(engine.rewind_to(Time::from(40)), Err(RewindError::InFuture));
+    assert!(engine.rewind_to(Time::from(12)).is_ok());
+}
+
+#[test]
+fn oldest_snapshots_are_dropped() {
+    let mut engine = setup(2);
+    brew(&mut engine, 50);
+
+    assert_eq!(engine.get_history_start(), Some(Time::from(30)));
+    assert_eq!(engine.rewind_to(Time::from(29)), Err(RewindError::TooOld));
+    assert!(engine.rewind_to(Time::from(30)).is_ok());
+}
+
+#[test]
+fn rewinding_keeps_schedule_and_heat_source_changes() {
+    let mut engine = setup(100);
+    engine.simulate(TimeSpan::from(5));
+
+    engine.schedule(
+        Time::from(8),
+        ScheduledAction::AddSubstance(
+            Essences::Vitae.into(),
+            Forms::Crystalline.into(),
+            Quantity::from(900),
+        ),
+    );
+    engine.heat_sources.push(HeatSource::new(Power::from(500)));
+    engine.simulate(TimeSpan::from(15));
+    let hash = engine.state_hash();
+    let temperature = engine.environment.temperature;
+
+    engine.rewind_to(Time::from(20)).unwrap();
+    assert_eq!(engine.state_hash(), hash);
+    assert_eq!(engine.environment.temperature, temperature);
+    assert_eq!(engine.heat_sources.len(), 1);
+}
@@ -0,0 +1,109 @@
+use data::essence::Essences;
+use data::form::Forms;
+use data::reactions::PyroflaxHeat;
+use essentia_rs::engine::builtin_reactions::{Dissolution, FormTransition};
+use essentia_rs::engine::{Essentia, EssentiaBuilder, OrderingError, ScheduledAction};
+use essentia_rs::physics::{Quantity, Time, TimeSpan};
+use essentia_rs::reaction::{DeclarativeReactionBuilder, ReactionId, Reagent, Stage};
+
+pub mod data;
+
+fn setup() -> Essentia {
+    // Create engine WITH built-in reactions
+    let mut builder = EssentiaBuilder::default();
+
+    builder = data::essence::create_essences()
+        .into_iter()
+        .fold(builder, |it, e| it.register_essence(e));
+
+    builder = data::form::create_forms()
+        .into_iter()
+        .fold(builder, |it, e| it.register_form(e));
+
+    let mut engine = builder
+        .register_reaction(Box::new(PyroflaxHeat::from(42)))
+        .with_history(TimeSpan::from(10), 10)
+        .build();
+
+    engine.perform(ScheduledAction::AddSubstance(
+        Essences::Aqua.into(),
+        Forms::Liquid.into(),
+        Quantity::from(5_000),
+    ));
+    engine.perform(ScheduledAction::AddSubstance(
+        Essences::Pyroflux.into(),
+        Forms::Salt.into(),
+        Quantity::from(10_000),
+    ));
+    engine
+}
+
+// The only reaction without a name is the pyroflux heat
+fn find_heat(engine: &Essentia) -> ReactionId {
+    engine
+        .list_reactions()
+        .into_iter()
+        .find(|info| info.name.is_none())
+        .unwrap()
+        .id
+}
+
+#[test]
+fn reactions_can_be_disabled_and_removed() {
+    let mut engine = setup();
+    let reactions = engine.list_reactions();
+    let dissolution = engine.find_reaction(Dissolution::NAME).unwrap();
+    let heat = find_heat(&engine);
+
+    assert_eq!(reactions.len(), 4);
+    assert_eq!(reactions[0].id, heat);
+    assert_eq!(
+        (reactions[0].stage, reactions[0].priority),
+        (Stage::Main, 100)
+    );
+    assert!(reactions
+        .iter()
+        .any(|info| info.id == dissolution && info.stage == Stage::Solubility));
+
+    let initial = engine.environment.temperature;
+    engine.simulate(TimeSpan::from(10));
+    let heated = engine.environment.temperature;
+    assert!(heated > initial);
+
+    // Ward is placed, pyroflux doesn't heat anymore
+    assert!(engine.set_reaction_enabled(heat, false));
+    engine.simulate(TimeSpan::from(10));
+    assert_eq!(engine.environment.temperature, heated);
+    assert!(!engine.list_reactions()[0].is_enabled);
+
+    // Rewinding brings back the reactions as they were
+    engine.rewind_to(Time::from(5)).unwrap();
+    assert!(engine.list_reactions()[0].is_enabled);
+
+    assert!(engine.remove_reaction(heat));
+    assert!(!engine.remove_reaction(heat));
+    assert_eq!(engine.list_reactions().len(), 3);
+}
+
+#[test]
+fn added_reactions_are_ordered_and_stay_in_their_engine() {
+    let mut engine = setup();
+    let fork = engine.fork();
+    let cooling = || {
+        DeclarativeReactionBuilder::default()
+            .with_reactant(Reagent::new(Essences::Pyroflux.into(), Forms::Salt.into()))
+            .with_product(Reagent::new(Essences::Heatstone.into(), Forms::Salt.into()))
+            .with_name("cooling")
+    };
+
+    let result = engine.add_reaction(Box::new(cooling().run_after(FormTransition::NAME).build()));
+    assert!(matches!(result, Err(OrderingError::StageConflict(_, _))));
+    assert_eq!(engine.list_reactions().len(), 4);
+
+    let id = engine
+        .add_reaction(Box::new(cooling().with_stage(Stage::Late).build()))
+        .unwrap();
+    assert_eq!(u64::from(id), 4);
+    assert_eq!(engine.list_reactions().last().unwrap().id, id);
+    assert_eq!(fork.list_reactions().len(), 4);
+}
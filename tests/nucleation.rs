@@ -0,0 +1,126 @@
+use data::form::Forms;
+use essentia_rs::engine::{Essentia, EssentiaBuilder};
+use essentia_rs::physics::{
+    Energy, Hysteresis, PhaseTransition, Quantity, SpecificHeatCapacity, Temperature, Time,
+    TimeSpan,
+};
+use essentia_rs::{EssenceBuilder, EssenceId, SubstanceBuilder};
+
+pub mod data;
+
+const FROSTWATER: u16 = 100;
+const FREEZING_TEMP: Temperature = Temperature { mkelvin: 273_000 };
+
+fn setup(temperature: Temperature) -> Essentia {
+    // Create engine WITH built-in reactions
+    setup_with(EssentiaBuilder::default(), temperature)
+}
+
+fn setup_with(mut builder: EssentiaBuilder, temperature: Temperature) -> Essentia {
+    builder = data::form::create_forms()
+        .into_iter()
+        .fold(builder, |it, e| it.register_form(e));
+
+    let mut engine = builder
+        .register_essence(
+            EssenceBuilder::default()
+                .with_name("Frostwater")
+                .with_custom_id(FROSTWATER.into())
+                .with_specific_heat_capacity(SpecificHeatCapacity::from(4))
+                .with_phase_transitions(|builder| {
+                    builder.add_transition(PhaseTransition {
+                        threshold: FREEZING_TEMP,
+                        joules_per_mol: Energy::from(8),
+                        left_form_id: Forms::Crystalline.into(),
+                        right_form_id: Forms::Liquid.into(),
+                        pressure_curve: None,
+                        hysteresis: Some(Hysteresis {
+                            superheating: Temperature::from(0),
+                            supercooling: Temperature::from(20_000),
+                        }),
+                    });
+                })
+                .build(),
+        )
+        .build();
+
+    engine.environment.temperature = temperature;
+    add_frostwater(&mut engine, Forms::Liquid, Quantity::from(10_000));
+    engine
+}
+
+fn add_frostwater(engine: &mut Essentia, form: Forms, quantity: Quantity) {
+    engine.add_substance(
+        SubstanceBuilder::new(engine)
+            .is_normal()
+            .with_essence(EssenceId::from(FROSTWATER))
+            .with_form(form.into())
+            .with_quantity(quantity)
+            .build(),
+    );
+}
+
+fn get_crystals(engine: &Essentia) -> Quantity {
+    engine.get_quantity_of(FROSTWATER.into(), Forms::Crystalline.into())
+}
+
+#[test]
+fn supercooled_liquid_stays_liquid() {
+    let mut engine = setup(Temperature::from(263_000));
+
+    engine.simulate(TimeSpan::from(10));
+
+    assert_eq!(get_crystals(&engine), Quantity::none());
+    assert_eq!(engine.environment.temperature, Temperature::from(263_000));
+}
+
+#[test]
+fn agitation_flash_freezes_supercooled_liquid() {
+    let mut engine = setup(Temperature::from(263_000));
+
+    engine.agitate();
+    engine.simulate(TimeSpan::default());
+
+    // Freezing releases latent heat, warming the system back towards the threshold
+    assert!(get_crystals(&engine) > Quantity::none());
+    assert!(engine.environment.temperature > Temperature::from(263_000));
+    assert!(engine.environment.temperature <= FREEZING_TEMP);
+}
+
+#[test]
+fn seed_crystal_triggers_nucleation() {
+    let mut engine = setup(Temperature::from(263_000));
+    add_frostwater(&mut engine, Forms::Crystalline, Quantity::from(1));
+
+    engine.simulate(TimeSpan::default());
+
+    assert!(get_crystals(&engine) > Quantity::from(1));
+}
+
+#[test]
+fn exceeding_margin_triggers_nucleation() {
+    let mut engine = setup(Temperature::from(250_000));
+
+    engine.simulate(TimeSpan::default());
+
+    assert!(get_crystals(&engine) > Quantity::none());
+}
+
+#[test]
+fn rewinding_replays_agitation() {
+    let builder = EssentiaBuilder::default().with_history(TimeSpan::from(10), 10);
+    let mut engine = setup_with(builder, Temperature::from(263_000));
+
+    let mut states = vec![];
+    for tick in 0..4 {
+        if tick == 2 {
+            engine.agitate();
+        }
+        engine.simulate(TimeSpan::from(1));
+        states.push((engine.state_hash(), get_crystals(&engine)));
+    }
+    assert!(states[2].1 > Quantity::none());
+
+    engine.rewind_to(Time::from(3)).unwrap();
+    assert_eq!((engine.state_hash(), get_crystals(&engine)), states[2]);
+}
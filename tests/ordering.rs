@@ -0,0 +1,88 @@
+use data::essence::Essences;
+use data::form::Forms;
+use essentia_rs::engine::builtin_reactions::{Dissolution, FormTransition, Precipitation};
+use essentia_rs::engine::{EssentiaBuilder, OrderingError};
+use essentia_rs::reaction::{DeclarativeReaction, DeclarativeReactionBuilder, Reagent, Stage};
+
+pub mod data;
+
+fn setup() -> EssentiaBuilder {
+    let mut builder = EssentiaBuilder::default();
+
+    builder = data::essence::create_essences()
+        .into_iter()
+        .fold(builder, |it, e| it.register_essence(e));
+
+    data::form::create_forms()
+        .into_iter()
+        .fold(builder, |it, e| it.register_form(e))
+}
+
+fn brewing() -> DeclarativeReactionBuilder {
+    DeclarativeReactionBuilder::default()
+        .with_reactant(Reagent::new(Essences::Pyroflux.into(), Forms::Salt.into()))
+        .with_product(Reagent::new(Essences::Heatstone.into(), Forms::Salt.into()))
+}
+
+fn boxed(reaction: DeclarativeReaction) -> Box<DeclarativeReaction> {
+    Box::new(reaction)
+}
+
+#[test]
+fn reactions_run_in_stages_and_after_each_other() {
+    let engine = setup()
+        .register_reaction(boxed(
+            brewing()
+                .with_name("settling")
+                .with_stage(Stage::Solubility)
+                .run_after(Dissolution::NAME)
+                .build(),
+        ))
+        .register_reaction(boxed(brewing().with_name("stirring").build()))
+        .build();
+
+    let names = engine
+        .iter_reactions()
+        .map(|reaction| reaction.get_name().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec![
+            "stirring",
+            Dissolution::NAME,
+            Precipitation::NAME,
+            "settling",
+            FormTransition::NAME
+        ]
+    );
+}
+
+#[test]
+fn ordering_cycles_fail_the_build() {
+    let result = setup()
+        .register_reaction(boxed(
+            brewing().with_name("stirring").run_after("heating").build(),
+        ))
+        .register_reaction(boxed(
+            brewing().with_name("heating").run_after("stirring").build(),
+        ))
+        .try_build();
+    assert!(matches!(result, Err(OrderingError::Cycle(_))));
+
+    // A reaction of the main stage can't wait for the form transitions
+    let result = setup()
+        .register_reaction(boxed(
+            brewing()
+                .with_name("cooling")
+                .run_after(FormTransition::NAME)
+                .build(),
+        ))
+        .try_build();
+    assert_eq!(
+        result.err(),
+        Some(OrderingError::StageConflict(
+            String::from("cooling"),
+            String::from(FormTransition::NAME)
+        ))
+    );
+}
@@ -0,0 +1,77 @@
+use data::essence::Essences;
+use data::form::Forms;
+use essentia_rs::engine::{Essentia, EssentiaBuilder, ReactionContext, ScheduledAction};
+use essentia_rs::physics::{Quantity, Ratio, TimeSpan};
+use essentia_rs::reaction::{Product, Reaction};
+
+pub mod data;
+
+// Pyroflux that sometimes flares up into heatstone
+struct VolatileFlare;
+
+impl Reaction for VolatileFlare {
+    fn react(&self, context: &ReactionContext) -> Vec<Product> {
+        let pyroflux = context
+            .engine
+            .get_quantity_of(Essences::Pyroflux.into(), Forms::Salt.into());
+
+        if pyroflux > Quantity::none() && context.chance(Ratio::new(1u64, 4u64)) {
+            vec![Product::Produce(
+                Essences::Heatstone.into(),
+                Forms::Salt.into(),
+                Quantity::from(1 + context.next_below(10)),
+            )]
+        } else {
+            vec![]
+        }
+    }
+
+    fn get_priority(&self) -> u8 {
+        100
+    }
+}
+
+fn setup(seed: u64) -> Essentia {
+    // Create engine without built-in reactions
+    let mut builder = EssentiaBuilder::new();
+
+    builder = data::essence::create_essences()
+        .into_iter()
+        .fold(builder, |it, e| it.register_essence(e));
+
+    builder = data::form::create_forms()
+        .into_iter()
+        .fold(builder, |it, e| it.register_form(e));
+
+    let mut engine = builder
+        .register_reaction(Box::new(VolatileFlare))
+        .with_seed(seed)
+        .build();
+    engine.perform(ScheduledAction::AddSubstance(
+        Essences::Pyroflux.into(),
+        Forms::Salt.into(),
+        Quantity::from(1000),
+    ));
+    engine
+}
+
+fn get_flares(seed: u64) -> Vec<Quantity> {
+    let mut engine = setup(seed);
+    (0..100)
+        .map(|_| {
+            engine.simulate(TimeSpan::from(1));
+            engine.get_quantity_of(Essences::Heatstone.into(), Forms::Salt.into())
+        })
+        .collect()
+}
+
+#[test]
+fn same_seed_gives_same_results() {
+    let flares = get_flares(7);
+
+    assert_eq!(flares, get_flares(7));
+    assert_ne!(flares, get_flares(8));
+    // Flares happen sometimes, but not every tick
+    assert!(flares.last().unwrap() > &Quantity::none());
+    assert!(flares.windows(2).any(|pair| pair[0] == pair[1]));
+}
@@ -0,0 +1,104 @@
+use data::essence::Essences;
+use data::form::Forms;
+use essentia_rs::engine::{
+    Condition, EnvironmentParameter, Essentia, EssentiaBuilder, RecipeBuilder, ScheduledAction,
+    StepOutcome, Verdict,
+};
+use essentia_rs::physics::{Quantity, Temperature, TimeSpan};
+
+pub mod data;
+
+fn setup() -> Essentia {
+    // Create engine WITH built-in reactions
+    let mut builder = EssentiaBuilder::default();
+
+    builder = data::essence::create_essences()
+        .into_iter()
+        .fold(builder, |it, e| it.register_essence(e));
+
+    builder = data::form::create_forms()
+        .into_iter()
+        .fold(builder, |it, e| it.register_form(e));
+
+    builder.build()
+}
+
+fn add(essence: Essences, form: Forms, quantity: Quantity) -> ScheduledAction {
+    ScheduledAction::AddSubstance(essence.into(), form.into(), quantity)
+}
+
+fn set_temperature(kelvin: f64) -> ScheduledAction {
+    ScheduledAction::SetEnvironment(EnvironmentParameter::Temperature(Temperature::from_kelvin(
+        kelvin,
+    )))
+}
+
+#[test]
+fn brewing_vitae_solution() {
+    let mut engine = setup();
+    let recipe = RecipeBuilder::default()
+        .with_action(add(Essences::Aqua, Forms::Liquid, Quantity::from(20_000)))
+        .with_action(set_temperature(350.0))
+        .with_action(add(
+            Essences::Vitae,
+            Forms::Crystalline,
+            Quantity::from(500),
+        ))
+        .with_wait_until(Condition::QuantityAtMost(
+            Essences::Vitae.into(),
+            Forms::Crystalline.into(),
+            Quantity::none(),
+        ))
+        .with_action(set_temperature(300.0))
+        .with_wait(TimeSpan::from(10))
+        .with_expectation(Condition::TotalAtLeast(
+            Essences::Vitae.into(),
+            Quantity::from(500),
+        ))
+        .with_expected_product(
+            Essences::Aqua.into(),
+            Forms::Liquid.into(),
+            Quantity::from(20_000),
+        )
+        .build();
+
+    let report = recipe.run(&mut engine);
+
+    assert_eq!(report.steps.len(), 6);
+    assert!(report
+        .steps
+        .iter()
+        .all(|step| step.outcome == StepOutcome::Done));
+    assert!(report.steps[3].finished_at > report.steps[3].started_at);
+    assert!(report.is_success(), "{:?}", report.verdict);
+}
+
+#[test]
+fn reports_timed_out_step_and_unmet_expectations() {
+    let mut engine = setup();
+    let never = Condition::TemperatureAtLeast(Temperature::from_kelvin(1000.0));
+    let recipe = RecipeBuilder::default()
+        .with_wait_until(never)
+        .with_wait(TimeSpan::from(10))
+        .with_timeout(TimeSpan::from(50))
+        .build();
+
+    let report = recipe.run(&mut engine);
+    assert_eq!(report.verdict, Verdict::TimedOut(0));
+    assert_eq!(report.steps.len(), 1);
+    assert_eq!(report.steps[0].finished_at.ticks, 50);
+
+    let expected = Condition::QuantityAtLeast(
+        Essences::Saline.into(),
+        Forms::Crystalline.into(),
+        Quantity::from(1),
+    );
+    let recipe = RecipeBuilder::default()
+        .with_wait(TimeSpan::from(10))
+        .with_expectation(expected)
+        .build();
+    assert_eq!(
+        recipe.run(&mut engine).verdict,
+        Verdict::Unmet(vec![expected])
+    );
+}
@@ -0,0 +1,106 @@
+use data::essence::Essences;
+use data::form::Forms;
+use data::reactions::{CryodustChill, PyroflaxHeat};
+use essentia_rs::engine::{Essentia, EssentiaBuilder, HeatSource, ScheduledAction, Source};
+use essentia_rs::physics::{Power, Quantity, Rate, TimeSpan};
+use essentia_rs::reaction::Product;
+
+pub mod data;
+
+fn setup() -> Essentia {
+    // Create engine WITH built-in reactions
+    let mut builder = EssentiaBuilder::default();
+
+    builder = data::essence::create_essences()
+        .into_iter()
+        .fold(builder, |it, e| it.register_essence(e));
+
+    builder = data::form::create_forms()
+        .into_iter()
+        .fold(builder, |it, e| it.register_form(e));
+
+    let mut engine = builder
+        .register_reaction(Box::new(PyroflaxHeat::from(42)))
+        .register_reaction(Box::new(CryodustChill::new(Power::from(40), Rate::from(1))))
+        .with_heat_source(HeatSource::new(Power::from(500)))
+        .with_reports()
+        .build();
+
+    engine.perform(ScheduledAction::AddSubstance(
+        Essences::Aqua.into(),
+        Forms::Liquid.into(),
+        Quantity::from(5_000),
+    ));
+    engine.perform(ScheduledAction::AddSubstance(
+        Essences::Pyroflux.into(),
+        Forms::Salt.into(),
+        Quantity::from(1_000),
+    ));
+    engine.perform(ScheduledAction::AddSubstance(
+        Essences::Cryodust.into(),
+        Forms::Salt.into(),
+        Quantity::from(2_000),
+    ));
+    engine
+}
+
+#[test]
+fn reports_attribute_products_to_their_sources() {
+    let mut engine = setup();
+    let ids = engine
+        .list_reactions()
+        .into_iter()
+        .filter(|info| info.name.is_none())
+        .map(|info| info.id)
+        .collect::<Vec<_>>();
+    let (heat, chill) = (Source::Reaction(ids[0]), Source::Reaction(ids[1]));
+
+    engine.simulate(TimeSpan::from(1));
+    let report = &engine.get_reports()[0];
+    let thermal = report.get_thermal_by_source();
+
+    assert_eq!(engine.get_reports().len(), 1);
+    assert_eq!(thermal[&Source::Injected], Power::from(500));
+    assert_eq!(thermal[&heat], Power::from(42_000));
+    assert_eq!(thermal[&chill], Power::from(-80_000));
+    assert!(report.iter_from(chill).any(|product| *product
+        == Product::Consume(
+            Essences::Cryodust.into(),
+            Forms::Salt.into(),
+            Quantity::from(1)
+        )));
+
+    // Contributions add up to the netted heat
+    let net = report
+        .products
+        .iter()
+        .find_map(|product| match product {
+            Product::Thermal(power) => Some(*power),
+            _ => None,
+        })
+        .unwrap();
+    let total = thermal
+        .values()
+        .fold(Power::from(0), |total, &power| total + power);
+    assert_eq!(total, net);
+}
+
+#[test]
+fn reports_are_kept_for_the_last_simulation_only() {
+    let mut engine = setup();
+    engine.schedule(
+        engine.environment.time + TimeSpan::from(2),
+        ScheduledAction::Agitate,
+    );
+
+    engine.simulate(TimeSpan::from(5));
+    let times = engine
+        .get_reports()
+        .iter()
+        .map(|report| report.time.ticks)
+        .collect::<Vec<_>>();
+    assert_eq!(times, vec![0, 2]);
+
+    engine.simulate(TimeSpan::from(1));
+    assert_eq!(engine.get_reports().len(), 1);
+}
@@ -0,0 +1,104 @@
+use essentia_rs::engine::{EnvironmentParameter, Essentia, EssentiaBuilder, ScheduledAction};
+use essentia_rs::physics::{Power, Quantity, Temperature, Time, TimeSpan};
+
+use crate::data::essence::Essences;
+use crate::data::form::Forms;
+
+pub mod data;
+
+fn setup() -> Essentia {
+    // Create engine without built-in reactions
+    let mut builder = EssentiaBuilder::new();
+
+    builder = data::essence::create_essences()
+        .into_iter()
+        .fold(builder, |it, e| it.register_essence(e));
+
+    builder = data::form::create_forms()
+        .into_iter()
+        .fold(builder, |it, e| it.register_form(e));
+
+    builder.build()
+}
+
+fn get_heatstone(engine: &Essentia) -> Quantity {
+    engine.get_quantity_of(Essences::Heatstone.into(), Forms::Salt.into())
+}
+
+#[test]
+fn substances_are_added_and_removed_on_time() {
+    let mut engine = setup();
+    engine.schedule(
+        Time::from(5),
+        ScheduledAction::AddSubstance(
+            Essences::Heatstone.into(),
+            Forms::Salt.into(),
+            Quantity::from(2000),
+        ),
+    );
+    engine.schedule(
+        Time::from(7),
+        ScheduledAction::RemoveSubstance(
+            Essences::Heatstone.into(),
+            Forms::Salt.into(),
+            Quantity::from(500),
+        ),
+    );
+
+    engine.simulate(TimeSpan::from(4));
+    assert_eq!(get_heatstone(&engine), Quantity::none());
+
+    // Action fires exactly at the end of this simulation
+    engine.simulate(TimeSpan::from(1));
+    assert_eq!(get_heatstone(&engine), Quantity::from(2000));
+
+    engine.simulate(TimeSpan::from(10));
+    assert_eq!(get_heatstone(&engine), Quantity::from(1500));
+    assert!(engine.schedule.is_empty());
+}
+
+#[test]
+fn thermal_injection_heats_only_within_its_window() {
+    let mut engine = setup();
+    engine.schedule(
+        Time::from(0),
+        ScheduledAction::AddSubstance(
+            Essences::Heatstone.into(),
+            Forms::Salt.into(),
+            Quantity::from(10_000),
+        ),
+    );
+    engine.schedule(
+        Time::from(10),
+        ScheduledAction::InjectThermal(Power::from(500_000), TimeSpan::from(20)),
+    );
+
+    engine.simulate(TimeSpan::from(10));
+    let before = engine.environment.temperature;
+
+    // Split across the window boundaries in one call
+    engine.simulate(TimeSpan::from(15));
+    let middle = engine.environment.temperature;
+    engine.simulate(TimeSpan::from(15));
+    let after = engine.environment.temperature;
+    engine.simulate(TimeSpan::from(10));
+
+    assert!(middle > before);
+    assert!(after > middle);
+    assert_eq!(engine.environment.temperature, after);
+}
+
+#[test]
+fn environment_is_changed_on_time() {
+    let mut engine = setup();
+    let temperature = Temperature::from_celsius(80.0);
+    engine.schedule(
+        Time::from(3),
+        ScheduledAction::SetEnvironment(EnvironmentParameter::Temperature(temperature)),
+    );
+
+    engine.simulate(TimeSpan::from(2));
+    assert_ne!(engine.environment.temperature, temperature);
+    engine.simulate(TimeSpan::from(2));
+    assert_eq!(engine.environment.temperature, temperature);
+}